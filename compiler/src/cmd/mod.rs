@@ -1,4 +1,6 @@
+#[cfg(feature = "rest")]
 pub mod build;
+#[cfg(feature = "rest")]
 pub mod check;
 pub mod init;
 pub mod new;
@@ -5,7 +5,7 @@ use libra::libra_types::account_address::AccountAddress;
 use crate::mv::dependence::loader::Loader;
 use crate::embedded::ds_loader::StateViewLoader;
 use std::collections::HashMap;
-use anyhow::Result;
+use anyhow::{Result, Error};
 use std::{env, fs};
 use std::path::{PathBuf, Path};
 use rand::Rng;
@@ -13,6 +13,8 @@ use crate::mv::builder::Builder;
 use crate::manifest::{MoveToml, Layout};
 use std::fs::OpenOptions;
 use std::io::Write;
+use libra::libra_vm::file_format::{CompiledModule, CompiledScript};
+use libra::bytecode_verifier::{VerifiedModule, VerifiedScript};
 
 #[derive(Clone)]
 pub struct Compiler<S: StateView + Clone> {
@@ -80,6 +82,29 @@ where
             .map(|(_, bytecode)| bytecode)
             .ok_or_else(|| anyhow!("Expected source map is not empty."))
     }
+
+    /// Compiles the source code and runs the bytecode verifier on the serialized result.
+    pub fn compile_and_verify(
+        &self,
+        code: &str,
+        address: Option<AccountAddress>,
+    ) -> Result<Vec<u8>> {
+        let bytecode = self.compile(code, address)?;
+        verify_bytecode(&bytecode)?;
+        Ok(bytecode)
+    }
+}
+
+/// Runs the bytecode verifier on the compiled script or module.
+/// On failure the error holds the `VMStatus` reported by the verifier.
+pub fn verify_bytecode(bytecode: &[u8]) -> Result<()> {
+    if let Ok(script) = CompiledScript::deserialize(bytecode) {
+        VerifiedScript::new(script).map_err(|(_, status)| Error::new(status))?;
+    } else {
+        let module = CompiledModule::deserialize(bytecode)?;
+        VerifiedModule::new(module).map_err(|(_, status)| Error::new(status))?;
+    }
+    Ok(())
 }
 
 pub struct TempDir {
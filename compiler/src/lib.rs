@@ -11,3 +11,4 @@ mod mv;
 pub use mv::*;
 pub use embedded::Compiler;
 pub use embedded::compile;
+pub use embedded::verify_bytecode;
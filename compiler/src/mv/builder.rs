@@ -94,8 +94,11 @@ where
         let address = self
             .address()?
             .map(|addr| AccountAddress::new(addr.to_u8()));
-        let source_imports =
-            extract_from_source(sources, address, self.print_err, self.shutdown_on_err)?;
+        let source_imports = match extract_from_source(sources, address, self.print_err) {
+            Ok(imports) => imports,
+            Err(_) if self.shutdown_on_err => std::process::exit(1),
+            Err(err) => return Err(err),
+        };
         let mut deps = HashMap::new();
 
         let mut dep_list = HashSet::new();
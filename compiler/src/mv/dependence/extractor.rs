@@ -1,44 +1,299 @@
-use anyhow::Result;
+use anyhow::{Result, Error};
 use libra::move_core_types::language_storage::ModuleId;
 use std::path::PathBuf;
+use std::fs;
+use std::hash::Hasher;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use twox_hash::XxHash64;
 use libra::move_lang::{parse_program, errors};
 use libra::move_lang::parser::ast::{Definition, ModuleDefinition, Script};
-use std::collections::HashSet;
+use std::collections::{HashSet, BTreeSet, BTreeMap, HashMap};
 use libra::move_core_types::identifier::Identifier;
 use libra::libra_types::account_address::AccountAddress;
+use libra::libra_types::access_path::AccessPath;
 use libra::move_lang::parser::ast::*;
 use libra::libra_vm::CompiledModule;
-use termcolor::{StandardStream, ColorChoice};
-use std::process::exit;
+use libra::libra_vm::file_format::SignatureToken;
+use libra::lcs;
+use termcolor::{StandardStream, ColorChoice, Buffer};
+use rayon::prelude::*;
 use crate::mv::builder::convert_path;
+use crate::mv::dependence::loader::BytecodeSource;
 
 /// Extract dependencies from source code.
 pub fn extract_from_source(
     targets: &[PathBuf],
     address: Option<AccountAddress>,
     print_err: bool,
-    shutdown_on_err: bool,
 ) -> Result<HashSet<ModuleId>> {
     let mut extractor = DefinitionUses::with_address(address);
-    let (files, pprog_and_comments_res) = parse_program(&convert_path(targets)?, &[])?;
+    extract_definitions(&convert_path(targets)?, print_err, &mut extractor)?;
+    Ok(extractor.imports())
+}
+
+/// Extract the dependency graph from source code.
+/// Maps each module defined in the targets to the modules it directly uses.
+pub fn extract_graph_from_source(
+    targets: &[PathBuf],
+    address: Option<AccountAddress>,
+    print_err: bool,
+) -> Result<HashMap<ModuleId, HashSet<ModuleId>>> {
+    let mut extractor = DefinitionUses::with_address(address);
+    extract_definitions(&convert_path(targets)?, print_err, &mut extractor)?;
+    Ok(extractor.graph())
+}
+
+/// Sorts the modules of the dependency graph so that every module follows its dependencies.
+/// Dependencies which are not defined in the graph are skipped.
+/// Returns an error if the modules depend on each other.
+pub fn sort_graph(graph: &HashMap<ModuleId, HashSet<ModuleId>>) -> Result<Vec<ModuleId>> {
+    let mut pending = graph
+        .iter()
+        .map(|(id, uses)| {
+            let deps = uses
+                .iter()
+                .filter(|dep| graph.contains_key(dep))
+                .collect::<HashSet<_>>();
+            (id, deps)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut sorted = Vec::with_capacity(graph.len());
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(id, _)| (*id).to_owned())
+            .collect::<HashSet<_>>();
+        if ready.is_empty() {
+            let cycle = detect_cycles(graph).unwrap_or_default();
+            bail!(
+                "Cyclic dependency between modules: {}.",
+                cycle
+                    .iter()
+                    .map(module_name)
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            );
+        }
+
+        pending.retain(|id, _| !ready.contains(id));
+        for deps in pending.values_mut() {
+            deps.retain(|dep| !ready.contains(dep));
+        }
+        sorted.extend(sort_imports(ready));
+    }
+
+    Ok(sorted)
+}
+
+/// Finds a cycle in the dependency graph.
+/// Returns the modules of the cycle in the use order, the last module uses the first one.
+pub fn detect_cycles(graph: &HashMap<ModuleId, HashSet<ModuleId>>) -> Option<Vec<ModuleId>> {
+    let mut visited = HashSet::new();
+    let mut stack = Vec::new();
+    for id in sort_imports(graph.keys().cloned().collect()) {
+        if let Some(cycle) = find_cycle(graph, &id, &mut visited, &mut stack) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+/// Renders the dependency graph as a Graphviz digraph.
+/// Nodes are labeled `address::Name`, every use is rendered as an edge.
+pub fn to_dot(graph: &HashMap<ModuleId, HashSet<ModuleId>>) -> String {
+    let mut dot = String::from("digraph {\n");
+    for id in sort_imports(graph.keys().cloned().collect()) {
+        dot.push_str(&format!("    \"{}\";\n", module_name(&id)));
+        for dep in sort_imports(graph[&id].to_owned()) {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                module_name(&id),
+                module_name(&dep)
+            ));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Depth-first search of a cycle. `stack` holds the path from the root to the current module.
+fn find_cycle(
+    graph: &HashMap<ModuleId, HashSet<ModuleId>>,
+    id: &ModuleId,
+    visited: &mut HashSet<ModuleId>,
+    stack: &mut Vec<ModuleId>,
+) -> Option<Vec<ModuleId>> {
+    if let Some(pos) = stack.iter().position(|module| module == id) {
+        return Some(stack[pos..].to_vec());
+    }
+    if !visited.insert(id.to_owned()) {
+        return None;
+    }
+
+    stack.push(id.to_owned());
+    if let Some(uses) = graph.get(id) {
+        for dep in sort_imports(uses.to_owned()) {
+            if let Some(cycle) = find_cycle(graph, &dep, visited, stack) {
+                return Some(cycle);
+            }
+        }
+    }
+    stack.pop();
+    None
+}
+
+/// Extract dependencies from source code.
+/// `on_import` is called once for every import as soon as it is found.
+/// Modules defined in the targets may be reported too, but they are excluded from the result.
+pub fn extract_from_source_with_progress<F: FnMut(&ModuleId)>(
+    targets: &[PathBuf],
+    address: Option<AccountAddress>,
+    print_err: bool,
+    on_import: F,
+) -> Result<HashSet<ModuleId>> {
+    let mut extractor = DefinitionUses::with_address(address).with_import_callback(on_import);
+    extract_definitions(&convert_path(targets)?, print_err, &mut extractor)?;
+    Ok(extractor.imports())
+}
+
+/// Extract dependencies from source code.
+/// Dependencies of the files which are not changed since the previous call are taken from the cache.
+pub fn extract_from_source_with_cache(
+    targets: &[PathBuf],
+    address: Option<AccountAddress>,
+    print_err: bool,
+    cache: &SourceUsesCache,
+) -> Result<HashSet<ModuleId>> {
+    let mut imports = HashSet::new();
+    let mut modules = HashSet::new();
+
+    for target in targets {
+        let key = (target.to_owned(), address, content_hash(&fs::read(target)?));
+        let uses = if let Some(uses) = cache.get(&key) {
+            uses
+        } else {
+            let mut extractor = DefinitionUses::with_address(address);
+            extract_definitions(
+                &convert_path(&[target.to_owned()])?,
+                print_err,
+                &mut extractor,
+            )?;
+            let uses = FileUses {
+                imports: extractor.imports,
+                modules: extractor.modules,
+            };
+            cache.put(key, uses.clone());
+            uses
+        };
+
+        imports.extend(uses.imports);
+        modules.extend(uses.modules);
+    }
+
+    Ok(imports.difference(&modules).cloned().collect())
+}
+
+/// Checks the targets without parsing them.
+/// Returns the targets which can't be passed to the parser with the reason.
+pub fn validate_targets(targets: &[PathBuf]) -> Vec<(PathBuf, String)> {
+    targets
+        .iter()
+        .filter_map(|target| {
+            let reason = if convert_path(&[target.to_owned()]).is_err() {
+                "path is not valid unicode"
+            } else if !target.exists() {
+                "file not found"
+            } else if !target.is_file() {
+                "not a file"
+            } else if target.extension().map(|ext| ext != "move").unwrap_or(true) {
+                "not a .move file"
+            } else {
+                return None;
+            };
+            Some((target.to_owned(), reason.to_owned()))
+        })
+        .collect()
+}
+
+/// Parses source files and passes their definitions to the extractor.
+/// Returns an error with the rendered diagnostics if the sources can't be parsed.
+/// If `print_err` is set, the diagnostics are also printed to stderr.
+fn extract_definitions(
+    targets: &[String],
+    print_err: bool,
+    extractor: &mut DefinitionUses,
+) -> Result<()> {
+    let (files, pprog_and_comments_res) = parse_program(targets, &[])?;
     match pprog_and_comments_res {
         Ok((program, _)) => {
             for def in program.source_definitions {
                 extractor.extract(&def)?;
             }
+            Ok(())
         }
         Err(errs) => {
             if print_err {
                 let mut writer = StandardStream::stderr(ColorChoice::Auto);
-                errors::output_errors(&mut writer, files, errs);
-            }
-            if shutdown_on_err {
-                exit(1);
+                errors::output_errors(&mut writer, files.clone(), errs.clone());
             }
+            let mut writer = Buffer::no_color();
+            errors::output_errors(&mut writer, files, errs);
+            Err(Error::msg(String::from_utf8(writer.into_inner())?))
         }
     }
+}
 
-    Ok(extractor.imports())
+/// Returns the hash of the file content.
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = XxHash64::default();
+    hasher.write(content);
+    hasher.finish()
+}
+
+/// Dependencies of a single source file.
+#[derive(Clone, Default)]
+struct FileUses {
+    imports: HashSet<ModuleId>,
+    modules: HashSet<ModuleId>,
+}
+
+/// Cache key: file path, account address and content hash.
+/// Bare modules are resolved with the account address, so the address is a part of the key.
+type CacheKey = (PathBuf, Option<AccountAddress>, u64);
+
+/// Shared cache of source file dependencies keyed by file path, account address and content hash.
+#[derive(Clone, Default)]
+pub struct SourceUsesCache {
+    inner: Arc<Mutex<SourceUsesCacheInner>>,
+}
+
+#[derive(Default)]
+struct SourceUsesCacheInner {
+    entries: HashMap<CacheKey, FileUses>,
+    hits: usize,
+}
+
+impl SourceUsesCache {
+    /// Returns the number of the cache hits.
+    pub fn hits(&self) -> usize {
+        self.inner.lock().unwrap().hits
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<FileUses> {
+        let mut inner = self.inner.lock().unwrap();
+        let uses = inner.entries.get(key).cloned();
+        if uses.is_some() {
+            inner.hits += 1;
+        }
+        uses
+    }
+
+    fn put(&self, key: CacheKey, uses: FileUses) {
+        self.inner.lock().unwrap().entries.insert(key, uses);
+    }
 }
 
 /// Extract dependencies from bytecode.
@@ -48,21 +303,171 @@ pub fn extract_from_bytecode(bytecode: &[u8]) -> Result<HashSet<ModuleId>> {
     Ok(extractor.imports())
 }
 
+/// Extract dependencies from several bytecode blobs in parallel.
+/// Returns an error with the indexes of all blobs which can't be deserialized.
+pub fn extract_from_bytecodes(blobs: &[Vec<u8>]) -> Result<HashSet<ModuleId>> {
+    let results = blobs
+        .par_iter()
+        .map(|bytecode| extract_from_bytecode(bytecode))
+        .collect::<Vec<_>>();
+
+    let mut imports = HashSet::new();
+    let mut errors = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(deps) => imports.extend(deps),
+            Err(err) => errors.push(format!("index {}: {}", index, err)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(imports)
+    } else {
+        Err(anyhow!("Invalid bytecode at {}.", errors.join("; ")))
+    }
+}
+
+/// Checks whether the module imports the given module.
+/// Stops at the first matching module handle.
+pub fn imports_module(bytecode: &[u8], id: &ModuleId) -> Result<bool> {
+    let module = CompiledModule::deserialize(bytecode)?;
+    let module = module.as_inner();
+    // Skip self module with 0 index.
+    Ok(module.module_handles.iter().skip(1).any(|handle| {
+        module.address_identifiers[handle.address.0 as usize] == *id.address()
+            && module.identifiers[handle.name.0 as usize].as_str() == id.name().as_str()
+    }))
+}
+
+/// Extract all direct and transitive dependencies of the bytecode.
+/// Dependencies are loaded from the bytecode source.
+pub fn extract_bytecode_tree<S: BytecodeSource>(
+    source: &S,
+    bytecode: &[u8],
+) -> Result<BTreeMap<ModuleId, Vec<u8>>> {
+    let mut deps = BTreeMap::new();
+    let mut queue = extract_from_bytecode(bytecode)?
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    while let Some(id) = queue.pop() {
+        if deps.contains_key(&id) {
+            continue;
+        }
+
+        let bytecode = source.load(&id)?;
+        queue.extend(
+            extract_from_bytecode(&bytecode)?
+                .into_iter()
+                .filter(|dep| !deps.contains_key(dep)),
+        );
+        deps.insert(id, bytecode);
+    }
+
+    Ok(deps)
+}
+
+/// Extract all direct and transitive dependencies of the source code.
+/// Dependencies of the imported modules are loaded from the bytecode source.
+/// Every module is loaded once, so import cycles don't loop forever.
+pub fn extract_transitive<S: BytecodeSource>(
+    targets: &[PathBuf],
+    address: Option<AccountAddress>,
+    source: &S,
+) -> Result<HashSet<ModuleId>> {
+    let mut deps = HashSet::new();
+    let mut queue = extract_from_source(targets, address, false)?
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    while let Some(id) = queue.pop() {
+        if deps.contains(&id) {
+            continue;
+        }
+
+        let bytecode = source.load(&id)?;
+        queue.extend(
+            extract_from_bytecode(&bytecode)?
+                .into_iter()
+                .filter(|dep| !deps.contains(dep)),
+        );
+        deps.insert(id);
+    }
+
+    Ok(deps)
+}
+
+/// Returns the code access paths of the modules sorted by address and name.
+/// The paths can be fetched from a data source in one batch with `multi_get`.
+pub fn code_access_paths(imports: &HashSet<ModuleId>) -> Vec<AccessPath> {
+    sort_imports(imports.clone())
+        .iter()
+        .map(AccessPath::code_access_path)
+        .collect()
+}
+
+/// Extract account addresses referenced by bytecode.
+/// Includes addresses of the module handles and address constants.
+pub fn extract_addresses(bytecode: &[u8]) -> Result<BTreeSet<AccountAddress>> {
+    let module = CompiledModule::deserialize(bytecode)?.into_inner();
+    let mut addresses = module
+        .address_identifiers
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+
+    for constant in &module.constant_pool {
+        match &constant.type_ {
+            SignatureToken::Address => {
+                addresses.insert(lcs::from_bytes(&constant.data)?);
+            }
+            SignatureToken::Vector(inner) if **inner == SignatureToken::Address => {
+                addresses.extend(lcs::from_bytes::<Vec<AccountAddress>>(&constant.data)?);
+            }
+            _ => { /*no-op*/ }
+        }
+    }
+
+    Ok(addresses)
+}
+
 /// Source definition dependencies extractor.
 #[derive(Default)]
-pub struct DefinitionUses {
+pub struct DefinitionUses<'a> {
     imports: HashSet<ModuleId>,
     modules: HashSet<ModuleId>,
+    graph: HashMap<ModuleId, HashSet<ModuleId>>,
+    module_uses: HashSet<ModuleId>,
     address: Option<AccountAddress>,
+    on_import: Option<Box<dyn FnMut(&ModuleId) + 'a>>,
 }
 
-impl DefinitionUses {
+impl<'a> DefinitionUses<'a> {
     /// Creates extractor with account address.
-    pub fn with_address(address: Option<AccountAddress>) -> DefinitionUses {
+    pub fn with_address(address: Option<AccountAddress>) -> DefinitionUses<'a> {
         DefinitionUses {
             imports: Default::default(),
             modules: Default::default(),
+            graph: Default::default(),
+            module_uses: Default::default(),
             address,
+            on_import: None,
+        }
+    }
+
+    /// Sets a callback which is called once for every import as soon as it is found.
+    pub fn with_import_callback<F: FnMut(&ModuleId) + 'a>(mut self, on_import: F) -> Self {
+        self.on_import = Some(Box::new(on_import));
+        self
+    }
+
+    /// Adds the import and reports it if it is new.
+    fn add_import(&mut self, module_id: ModuleId) {
+        self.module_uses.insert(module_id.clone());
+        if !self.imports.contains(&module_id) {
+            if let Some(on_import) = &mut self.on_import {
+                on_import(&module_id);
+            }
+            self.imports.insert(module_id);
         }
     }
 
@@ -87,6 +492,7 @@ impl DefinitionUses {
 
     /// Extracts dependencies from module definition.
     fn module(&mut self, module: &ModuleDefinition, address: AccountAddress) -> Result<()> {
+        self.module_uses.clear();
         for member in &module.members {
             match member {
                 ModuleMember::Use(_use) => self.uses(_use)?,
@@ -108,10 +514,14 @@ impl DefinitionUses {
                 }
             }
         }
-        self.modules.insert(ModuleId::new(
-            address,
-            Identifier::new(module.name.0.value.to_owned())?,
-        ));
+        let module_id = ModuleId::new(address, Identifier::new(module.name.0.value.to_owned())?);
+        let mut uses = mem::take(&mut self.module_uses);
+        uses.remove(&module_id);
+        self.graph
+            .entry(module_id.clone())
+            .or_default()
+            .extend(uses);
+        self.modules.insert(module_id);
 
         Ok(())
     }
@@ -134,7 +544,7 @@ impl DefinitionUses {
         let ident = &ident.0.value;
         let name = Identifier::new(ident.name.0.value.to_owned())?;
         let address = AccountAddress::new(ident.address.clone().to_u8());
-        self.imports.insert(ModuleId::new(address, name));
+        self.add_import(ModuleId::new(address, name));
         Ok(())
     }
 
@@ -253,7 +663,7 @@ impl DefinitionUses {
         match access {
             ModuleAccess_::QualifiedModuleAccess(ident, _name) => {
                 let ident = &ident.0.value;
-                self.imports.insert(ModuleId::new(
+                self.add_import(ModuleId::new(
                     AccountAddress::new(ident.address.clone().to_u8()),
                     Identifier::new(ident.name.0.value.to_owned())?,
                 ));
@@ -403,6 +813,23 @@ impl DefinitionUses {
 
         self.imports
     }
+
+    /// Returns imports needed outside of tests.
+    /// The Move dialect has no test-only `use` statements, so all imports are returned.
+    pub fn imports_excluding_test(self) -> HashSet<ModuleId> {
+        self.imports()
+    }
+
+    /// Returns imports sorted by address and name.
+    pub fn sorted_imports(self) -> Vec<ModuleId> {
+        sort_imports(self.imports())
+    }
+
+    /// Returns the dependency graph of the defined modules.
+    /// Maps each module to the modules it directly uses. Scripts are not included.
+    pub fn graph(self) -> HashMap<ModuleId, HashSet<ModuleId>> {
+        self.graph
+    }
 }
 
 /// Bytecode dependencies extractor.
@@ -417,6 +844,11 @@ impl BytecodeUses {
         self.imports
     }
 
+    /// Returns imports sorted by address and name.
+    pub fn sorted_imports(self) -> Vec<ModuleId> {
+        sort_imports(self.imports)
+    }
+
     /// Extracts dependencies from compiled module.
     pub fn extract(&mut self, module: CompiledModule) -> Result<()> {
         let module = module.into_inner();
@@ -438,3 +870,530 @@ impl BytecodeUses {
         Ok(())
     }
 }
+
+/// Sorts module identifiers by address and name.
+fn sort_imports(imports: HashSet<ModuleId>) -> Vec<ModuleId> {
+    let mut imports = imports.into_iter().collect::<Vec<_>>();
+    imports.sort_by(|a, b| {
+        a.address()
+            .cmp(b.address())
+            .then_with(|| a.name().cmp(b.name()))
+    });
+    imports
+}
+
+/// Returns the module name in the `address::Name` form.
+fn module_name(id: &ModuleId) -> String {
+    format!("{}::{}", id.address(), id.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use ds::MockDataSource;
+    use libra::libra_types::account_address::AccountAddress;
+    use libra::libra_vm::CompiledModule;
+    use libra::move_core_types::identifier::Identifier;
+    use libra::move_core_types::language_storage::{ModuleId, CORE_CODE_ADDRESS};
+    use crate::embedded::Compiler;
+    use crate::mv::dependence::extractor::{
+        BytecodeUses, extract_addresses, extract_from_source_with_cache, SourceUsesCache,
+        code_access_paths, validate_targets, imports_module, DefinitionUses, extract_definitions,
+        extract_from_source_with_progress, extract_transitive, extract_graph_from_source,
+        sort_graph, detect_cycles, extract_from_source, to_dot, extract_from_bytecodes,
+    };
+    use crate::mv::dependence::loader::InMemorySource;
+    use libra::libra_types::access_path::AccessPath;
+    use std::collections::{HashMap, HashSet};
+    use crate::embedded::TempDir;
+    use std::fs;
+
+    fn module_id(address: AccountAddress, name: &str) -> ModuleId {
+        ModuleId::new(address, Identifier::new(name).unwrap())
+    }
+
+    #[test]
+    fn test_sorted_imports() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let other_address = AccountAddress::new([0x2; 20]);
+        for (source, address) in &[
+            ("module B { struct T { f: u64 } }", CORE_CODE_ADDRESS),
+            ("module A { struct T { f: u64 } }", CORE_CODE_ADDRESS),
+            ("module A { struct T { f: u64 } }", other_address),
+        ] {
+            ds.publish_module(compiler.compile(source, Some(*address)).unwrap())
+                .unwrap();
+        }
+
+        let source = "
+            module M {
+                use 0x2::A as OtherA;
+                use 0x1::B;
+                use 0x1::A;
+
+                struct T {
+                    a: OtherA::T,
+                    b: B::T,
+                    c: A::T,
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut extractor = BytecodeUses::default();
+        extractor
+            .extract(CompiledModule::deserialize(&bytecode).unwrap())
+            .unwrap();
+        assert_eq!(
+            extractor.sorted_imports(),
+            vec![
+                module_id(CORE_CODE_ADDRESS, "A"),
+                module_id(CORE_CODE_ADDRESS, "B"),
+                module_id(other_address, "A"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_imports_module() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile("module A { struct T { f: u64 } }", Some(CORE_CODE_ADDRESS))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::A;
+
+                struct T { a: A::T }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        assert!(imports_module(&bytecode, &module_id(CORE_CODE_ADDRESS, "A")).unwrap());
+        assert!(!imports_module(&bytecode, &module_id(CORE_CODE_ADDRESS, "B")).unwrap());
+        let other_address = AccountAddress::new([0x2; 20]);
+        assert!(!imports_module(&bytecode, &module_id(other_address, "A")).unwrap());
+        assert!(!imports_module(&bytecode, &module_id(CORE_CODE_ADDRESS, "M")).unwrap());
+    }
+
+    #[test]
+    fn test_imports_excluding_test() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let source = "
+            module M {
+                use 0x1::A;
+                use 0x2::B;
+
+                public fun f(): u64 { A::f() + B::f() }
+            }
+        ";
+        fs::write(&path, source).unwrap();
+
+        let mut extractor = DefinitionUses::with_address(Some(CORE_CODE_ADDRESS));
+        let targets = vec![path.to_string_lossy().to_string()];
+        extract_definitions(&targets, false, &mut extractor).unwrap();
+        let mut imports = extractor
+            .imports_excluding_test()
+            .into_iter()
+            .collect::<Vec<_>>();
+        imports.sort();
+        assert_eq!(
+            imports,
+            vec![
+                module_id(CORE_CODE_ADDRESS, "A"),
+                module_id(AccountAddress::from_hex_literal("0x2").unwrap(), "B"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_with_progress() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let source = "
+            module M {
+                use 0x1::A;
+                use 0x2::B;
+
+                public fun f(): u64 { A::f() + B::f() + 0x1::A::g() }
+            }
+        ";
+        fs::write(&path, source).unwrap();
+
+        let mut reported = vec![];
+        let imports = extract_from_source_with_progress(
+            &[path],
+            Some(CORE_CODE_ADDRESS),
+            false,
+            |module_id| reported.push(module_id.to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            reported,
+            vec![
+                module_id(CORE_CODE_ADDRESS, "A"),
+                module_id(AccountAddress::from_hex_literal("0x2").unwrap(), "B"),
+            ]
+        );
+        assert_eq!(imports, reported.into_iter().collect::<HashSet<_>>());
+    }
+
+    #[test]
+    fn test_extract_transitive() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let mut source = InMemorySource::new();
+        for (name, code) in &[
+            ("C", "module C { public fun f(): u64 { 1 } }"),
+            (
+                "B",
+                "module B { use 0x1::C; public fun f(): u64 { C::f() } }",
+            ),
+            (
+                "A",
+                "module A { use 0x1::B; public fun f(): u64 { B::f() } }",
+            ),
+        ] {
+            let bytecode = compiler.compile(code, Some(CORE_CODE_ADDRESS)).unwrap();
+            ds.publish_module(bytecode.clone()).unwrap();
+            source.add(module_id(CORE_CODE_ADDRESS, name), bytecode);
+        }
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let code = "
+            module M {
+                use 0x1::A;
+
+                public fun f(): u64 { A::f() }
+            }
+        ";
+        fs::write(&path, code).unwrap();
+
+        let deps = extract_transitive(&[path], Some(CORE_CODE_ADDRESS), &source).unwrap();
+        assert_eq!(
+            deps,
+            vec![
+                module_id(CORE_CODE_ADDRESS, "A"),
+                module_id(CORE_CODE_ADDRESS, "B"),
+                module_id(CORE_CODE_ADDRESS, "C"),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let source = "
+            module B {
+                use 0x1::A;
+
+                public fun f(): u64 { A::f() }
+            }
+
+            module A {
+                use 0x1::Coin;
+
+                public fun f(): u64 { Coin::value() }
+            }
+        ";
+        fs::write(&path, source).unwrap();
+
+        let graph = extract_graph_from_source(&[path], Some(CORE_CODE_ADDRESS), false).unwrap();
+        let a = module_id(CORE_CODE_ADDRESS, "A");
+        let b = module_id(CORE_CODE_ADDRESS, "B");
+        let coin = module_id(CORE_CODE_ADDRESS, "Coin");
+        assert_eq!(graph.len(), 2);
+        assert_eq!(graph[&b], vec![a.clone()].into_iter().collect());
+        assert_eq!(graph[&a], vec![coin].into_iter().collect());
+        assert_eq!(sort_graph(&graph).unwrap(), vec![a.clone(), b.clone()]);
+
+        let mut cyclic = graph;
+        cyclic.get_mut(&a).unwrap().insert(b);
+        assert!(sort_graph(&cyclic).is_err());
+    }
+
+    #[test]
+    fn test_detect_cycles() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let source = "
+            module A {
+                use 0x1::B;
+
+                public fun f(): u64 { B::f() }
+            }
+
+            module B {
+                use 0x1::C;
+
+                public fun f(): u64 { C::f() }
+            }
+
+            module C {
+                use 0x1::A;
+
+                public fun f(): u64 { A::f() }
+            }
+        ";
+        fs::write(&path, source).unwrap();
+
+        let mut graph = extract_graph_from_source(&[path], Some(CORE_CODE_ADDRESS), false).unwrap();
+        let a = module_id(CORE_CODE_ADDRESS, "A");
+        let b = module_id(CORE_CODE_ADDRESS, "B");
+        let c = module_id(CORE_CODE_ADDRESS, "C");
+        assert_eq!(detect_cycles(&graph), Some(vec![a.clone(), b, c.clone()]));
+
+        graph.get_mut(&c).unwrap().remove(&a);
+        assert_eq!(detect_cycles(&graph), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        fs::write(&path, "module M { public fun f(): u64 { 1 }").unwrap();
+
+        let err = extract_from_source(&[path], Some(CORE_CODE_ADDRESS), false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("m.move"));
+        assert!(err.contains("Unexpected"));
+    }
+
+    #[test]
+    fn test_extract_from_bytecodes() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        for source in &[
+            "module A { struct T { f: u64 } }",
+            "module B { struct T { f: u64 } }",
+        ] {
+            ds.publish_module(compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap())
+                .unwrap();
+        }
+
+        let blobs = vec![
+            compiler
+                .compile(
+                    "module C { use 0x1::A; struct T { a: A::T } }",
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+            compiler
+                .compile(
+                    "module D { use 0x1::B; struct T { b: B::T } }",
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        ];
+        assert_eq!(
+            extract_from_bytecodes(&blobs).unwrap(),
+            vec![
+                module_id(CORE_CODE_ADDRESS, "A"),
+                module_id(CORE_CODE_ADDRESS, "B"),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+
+        let mut blobs = blobs;
+        blobs.insert(1, vec![0x1, 0x2, 0x3]);
+        let err = extract_from_bytecodes(&blobs).unwrap_err().to_string();
+        assert!(err.contains("index 1:"));
+        assert!(!err.contains("index 0:"));
+        assert!(!err.contains("index 2:"));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let a = module_id(CORE_CODE_ADDRESS, "A");
+        let b = module_id(CORE_CODE_ADDRESS, "B");
+        let mut graph = HashMap::new();
+        graph.insert(a.clone(), HashSet::new());
+        graph.insert(b, vec![a].into_iter().collect());
+
+        let dot = to_dot(&graph);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        let node = format!("    \"{}::A\";\n", CORE_CODE_ADDRESS);
+        assert!(dot.contains(&node));
+        let edge = format!("    \"{0}::B\" -> \"{0}::A\";\n", CORE_CODE_ADDRESS);
+        assert!(dot.contains(&edge));
+    }
+
+    #[test]
+    fn test_qualified_names() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("m.move");
+        let source = "
+            module M {
+                public fun f(): u64 {
+                    let max = 0x2::Limits::max;
+                    0x1::Coin::value() + max
+                }
+            }
+        ";
+        fs::write(&path, source).unwrap();
+
+        let imports = extract_from_source(&[path], Some(CORE_CODE_ADDRESS), false).unwrap();
+        assert_eq!(
+            imports,
+            vec![
+                module_id(CORE_CODE_ADDRESS, "Coin"),
+                module_id(AccountAddress::from_hex_literal("0x2").unwrap(), "Limits"),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn test_validate_targets() {
+        let dir = TempDir::new().unwrap();
+        let valid = dir.path().join("valid.move");
+        fs::write(&valid, "module M { }").unwrap();
+        let text = dir.path().join("notes.txt");
+        fs::write(&text, "module M { }").unwrap();
+        let missing = dir.path().join("missing.move");
+        let sub_dir = dir.path().join("sources.move");
+        fs::create_dir(&sub_dir).unwrap();
+
+        assert!(validate_targets(&[valid.clone()]).is_empty());
+        assert_eq!(
+            validate_targets(&[valid, text.clone(), missing.clone(), sub_dir.clone()]),
+            vec![
+                (text, "not a .move file".to_owned()),
+                (missing, "file not found".to_owned()),
+                (sub_dir, "not a file".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_access_paths() {
+        let other_address = AccountAddress::new([0x2; 20]);
+        let imports = vec![
+            module_id(other_address, "A"),
+            module_id(CORE_CODE_ADDRESS, "B"),
+            module_id(CORE_CODE_ADDRESS, "A"),
+        ]
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+        let paths = code_access_paths(&imports);
+        assert_eq!(
+            paths,
+            vec![
+                AccessPath::code_access_path(&module_id(CORE_CODE_ADDRESS, "A")),
+                AccessPath::code_access_path(&module_id(CORE_CODE_ADDRESS, "B")),
+                AccessPath::code_access_path(&module_id(other_address, "A")),
+            ]
+        );
+        assert_eq!(paths[0].address, CORE_CODE_ADDRESS);
+        assert_eq!(paths[2].address, other_address);
+        assert_ne!(paths[0].path, paths[1].path);
+
+        assert!(code_access_paths(&HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_extract_addresses() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let dep_address = AccountAddress::from_hex_literal("0x2").unwrap();
+        ds.publish_module(
+            compiler
+                .compile("module A { struct T { f: u64 } }", Some(dep_address))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x2::A;
+
+                resource struct R { a: A::T }
+
+                public fun is_published(): bool {
+                    exists<R>(0x3)
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        assert_eq!(
+            extract_addresses(&bytecode)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                CORE_CODE_ADDRESS,
+                dep_address,
+                AccountAddress::from_hex_literal("0x3").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_uses_cache() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("source.move");
+        let cache = SourceUsesCache::default();
+
+        fs::write(&path, "module M { use 0x1::A; struct T { a: A::T } }").unwrap();
+        let imports =
+            extract_from_source_with_cache(&[path.clone()], Some(CORE_CODE_ADDRESS), false, &cache)
+                .unwrap();
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(
+            extract_from_source_with_cache(
+                &[path.clone()],
+                Some(CORE_CODE_ADDRESS),
+                false,
+                &cache,
+            )
+            .unwrap(),
+            imports
+        );
+        assert_eq!(cache.hits(), 1);
+
+        fs::write(&path, "module M { use 0x1::B; struct T { b: B::T } }").unwrap();
+        let imports =
+            extract_from_source_with_cache(&[path], Some(CORE_CODE_ADDRESS), false, &cache)
+                .unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(
+            imports.into_iter().collect::<Vec<_>>(),
+            vec![module_id(CORE_CODE_ADDRESS, "B")]
+        );
+
+        fs::write(
+            &path,
+            "module A { struct T { f: u64 } } module M { use 0x1::A; struct T { a: A::T } }",
+        )
+        .unwrap();
+        let imports =
+            extract_from_source_with_cache(&[path.clone()], Some(CORE_CODE_ADDRESS), false, &cache)
+                .unwrap();
+        assert!(imports.is_empty());
+        let other_address = AccountAddress::new([0x2; 20]);
+        let imports =
+            extract_from_source_with_cache(&[path], Some(other_address), false, &cache).unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(
+            imports.into_iter().collect::<Vec<_>>(),
+            vec![module_id(CORE_CODE_ADDRESS, "A")]
+        );
+    }
+}
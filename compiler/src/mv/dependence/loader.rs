@@ -1,13 +1,16 @@
 use std::path::{PathBuf, Path};
 use anyhow::Result;
 use libra::move_core_types::language_storage::ModuleId;
+#[cfg(feature = "rest")]
 use libra::libra_types::access_path::AccessPath;
 use tiny_keccak::{Hasher, Sha3};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
+#[cfg(feature = "rest")]
 use http::Uri;
 use crate::manifest::MoveToml;
 use std::fs;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 pub trait BytecodeSource: Clone {
@@ -23,17 +26,50 @@ impl BytecodeSource for ZeroSource {
     }
 }
 
+/// Bytecode source backed by an in-memory map of modules.
+#[derive(Clone, Default)]
+pub struct InMemorySource {
+    modules: HashMap<ModuleId, Vec<u8>>,
+}
+
+impl InMemorySource {
+    pub fn new() -> InMemorySource {
+        Default::default()
+    }
+
+    /// Adds the module bytecode.
+    pub fn add(&mut self, module_id: ModuleId, bytecode: Vec<u8>) {
+        self.modules.insert(module_id, bytecode);
+    }
+}
+
+impl BytecodeSource for InMemorySource {
+    fn load(&self, module_id: &ModuleId) -> Result<Vec<u8>> {
+        self.modules.get(module_id).cloned().ok_or_else(|| {
+            anyhow!(
+                "Module '0x{}::{}' not found",
+                module_id.address(),
+                module_id.name()
+            )
+        })
+    }
+}
+
+/// Bytecode source backed by the blockchain REST API.
+#[cfg(feature = "rest")]
 #[derive(Clone)]
 pub struct RestBytecodeSource {
     url: Uri,
 }
 
+#[cfg(feature = "rest")]
 impl RestBytecodeSource {
     pub fn new(url: Uri) -> RestBytecodeSource {
         RestBytecodeSource { url }
     }
 }
 
+#[cfg(feature = "rest")]
 impl BytecodeSource for RestBytecodeSource {
     fn load(&self, module_id: &ModuleId) -> Result<Vec<u8>> {
         let path = AccessPath::code_access_path(module_id);
@@ -122,6 +158,7 @@ where
     }
 }
 
+#[cfg(feature = "rest")]
 pub fn make_rest_loader(
     project_dir: &Path,
     cmove: &MoveToml,
@@ -1,2 +1,3 @@
 pub mod extractor;
 pub mod loader;
+pub mod version;
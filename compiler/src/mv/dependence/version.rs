@@ -0,0 +1,104 @@
+use anyhow::Result;
+use libra::libra_vm::file_format_common::BinaryConstants;
+use libra::move_core_types::language_storage::ModuleId;
+use crate::mv::dependence::extractor::extract_from_bytecode;
+use crate::mv::dependence::loader::BytecodeSource;
+
+/// Returns the binary format version of the module.
+/// The version is stored as little-endian `u32` right after the magic.
+pub fn module_version(bytecode: &[u8]) -> Result<u32> {
+    let magic_size = BinaryConstants::LIBRA_MAGIC_SIZE;
+    if bytecode.len() < magic_size + 4 || bytecode[..magic_size] != BinaryConstants::LIBRA_MAGIC {
+        bail!("Invalid module header.");
+    }
+
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytecode[magic_size..magic_size + 4]);
+    Ok(u32::from_le_bytes(version))
+}
+
+/// Dependency which binary format version differs from the version of the importer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VersionMismatch {
+    pub module_id: ModuleId,
+    pub version: u32,
+    pub expected: u32,
+}
+
+/// Loads the module dependencies and returns the ones with a binary format version
+/// different from the version of the module.
+pub fn check_dependency_versions<S: BytecodeSource>(
+    source: &S,
+    bytecode: &[u8],
+) -> Result<Vec<VersionMismatch>> {
+    let expected = module_version(bytecode)?;
+    let mut imports = extract_from_bytecode(bytecode)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    imports.sort_by(|a, b| (a.address(), a.name()).cmp(&(b.address(), b.name())));
+
+    let mut mismatches = vec![];
+    for module_id in imports {
+        let version = module_version(&source.load(&module_id)?)?;
+        if version != expected {
+            mismatches.push(VersionMismatch {
+                module_id,
+                version,
+                expected,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use ds::MockDataSource;
+    use libra::libra_vm::file_format_common::BinaryConstants;
+    use libra::move_core_types::identifier::Identifier;
+    use libra::move_core_types::language_storage::{ModuleId, CORE_CODE_ADDRESS};
+    use crate::embedded::Compiler;
+    use crate::mv::dependence::loader::InMemorySource;
+    use crate::mv::dependence::version::{check_dependency_versions, module_version, VersionMismatch};
+
+    #[test]
+    fn test_dependency_versions() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let id = |name: &str| ModuleId::new(CORE_CODE_ADDRESS, Identifier::new(name).unwrap());
+        let compile = |source: &str| compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut source = InMemorySource::new();
+        let a = compile("module A { struct T { f: u64 } }");
+        source.add(id("A"), a.clone());
+        let b = compile("module B { struct T { f: u64 } }");
+        source.add(id("B"), b.clone());
+        // Dependencies are resolved through the source, so the importer is compiled against the mock.
+        let ds = MockDataSource::new();
+        ds.publish_module(a).unwrap();
+        ds.publish_module(b.clone()).unwrap();
+        let m = Compiler::new(ds)
+            .compile(
+                "module M { use 0x1::A; use 0x1::B; struct T { a: A::T, b: B::T } }",
+                Some(CORE_CODE_ADDRESS),
+            )
+            .unwrap();
+
+        assert!(check_dependency_versions(&source, &m).unwrap().is_empty());
+
+        let version = module_version(&m).unwrap();
+        let magic_size = BinaryConstants::LIBRA_MAGIC_SIZE;
+        let mut newer_b = b;
+        newer_b[magic_size..magic_size + 4].copy_from_slice(&(version + 1).to_le_bytes());
+        source.add(id("B"), newer_b);
+
+        assert_eq!(
+            check_dependency_versions(&source, &m).unwrap(),
+            vec![VersionMismatch {
+                module_id: id("B"),
+                version: version + 1,
+                expected: version,
+            }]
+        );
+        assert!(module_version(&[0x1, 0x2]).is_err());
+    }
+}
@@ -1,17 +1,34 @@
 use anyhow::Result;
-use libra::libra_vm::CompiledModule;
+use libra::libra_vm::{CompiledModule, file_format::CompiledScript};
 use std::fmt::Display;
 use serde::export::Formatter;
 use core::fmt;
-use std::collections::BTreeMap;
-use libra::move_core_types::language_storage::ModuleId;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use twox_hash::XxHash64;
+use libra::move_core_types::language_storage::{ModuleId, TypeTag, StructTag};
+use libra::move_core_types::identifier::Identifier;
 use libra::libra_vm::file_format::{
     StructFieldInformation, Kind, SignatureToken, StructHandleIndex, CompiledModuleMut, Signature,
+    ModuleHandleIndex, StructDefinition, FunctionDefinition, Bytecode, FunctionHandleIndex,
+    StructDefinitionIndex, SignatureIndex, FieldHandleIndex, IdentifierIndex,
 };
 use libra::libra_types::account_address::AccountAddress;
+use libra::libra_types::transaction::TransactionArgument;
+use crate::mv::dependence::extractor::{extract_from_bytecode, BytecodeUses};
+use crate::mv::dependence::loader::BytecodeSource;
+use serde::Serialize;
+use libra::bytecode_source_map::source_map::ModuleSourceMap;
 
 const PHANTOM_RESOURCE_NAME: &str = "X_phantom_resource_X_";
 const GENERIC_PREFIX: &str = "__G_";
+const ARG_PREFIX: &str = "_arg_";
+const ABI_GENERIC_PREFIX: &str = "T";
+const EMIT_EVENT_NAME: &str = "emit_event";
 
 /// Disassembler configuration.
 pub struct Config<'a> {
@@ -20,8 +37,47 @@ pub struct Config<'a> {
     phantom_resource_name: &'a str,
     /// Generic name prefix.
     generic_prefix: &'a str,
+    /// Use the generic prefix as is instead of a prefix that can't collide with module identifiers.
+    /// Only for outputs which are not compiled back, such as the ABI.
+    exact_generic_prefix: bool,
     /// Disassemble only module interface.
     only_interface: bool,
+    /// Specification blocks keyed by function name.
+    /// Specs are not stored in the bytecode, so they are emitted as comments above the functions.
+    specs: Option<&'a BTreeMap<String, String>>,
+    /// Attributes keyed by function name.
+    /// Attributes are not stored in the bytecode, so they are supplied by the caller.
+    attributes: Option<&'a BTreeMap<String, Vec<String>>>,
+    /// New names of the module structs and functions keyed by their original names.
+    renames: Option<&'a BTreeMap<String, String>>,
+    /// Fail on any construct that can't be reproduced exactly instead of emitting an approximation.
+    strict: bool,
+    /// Emit the module in a single line.
+    compact: bool,
+    /// Number of spaces per indentation level.
+    indent: usize,
+    /// Annotate structs and functions with the indices of their handles.
+    handle_indices: bool,
+    /// Annotate structs and functions with their visibility.
+    visibility_comments: bool,
+    /// Disassemble only the functions which names match the filter.
+    function_filter: Option<&'a dyn Fn(&str) -> bool>,
+    /// Put a comma after the last struct field.
+    trailing_comma: bool,
+    /// Pad struct field names so that the field types line up.
+    align_fields: bool,
+    /// Emit the function instructions as comments inside the function bodies.
+    bytecode_listing: bool,
+    /// Banner emitted as line comments before the module.
+    header: Option<&'a str>,
+    /// Maximum size of the bytecode in bytes.
+    max_bytecode_size: Option<usize>,
+    /// Maximum number of struct definitions.
+    max_structs: Option<usize>,
+    /// Maximum number of function definitions.
+    max_functions: Option<usize>,
+    /// Original names of the type parameters and function parameters taken from a source map.
+    source_names: Option<SourceNames>,
 }
 
 impl<'a> Config<'a> {
@@ -34,9 +90,172 @@ impl<'a> Config<'a> {
         Self {
             phantom_resource_name,
             generic_prefix: generic_template,
+            exact_generic_prefix: false,
             only_interface,
+            specs: None,
+            attributes: None,
+            renames: None,
+            strict: false,
+            compact: false,
+            indent: 4,
+            handle_indices: false,
+            visibility_comments: false,
+            function_filter: None,
+            trailing_comma: true,
+            align_fields: false,
+            bytecode_listing: false,
+            header: None,
+            max_bytecode_size: None,
+            max_structs: None,
+            max_functions: None,
+            source_names: None,
         }
     }
+
+    /// Sets specification blocks to be emitted as comments above the functions.
+    pub fn with_specs(mut self, specs: &'a BTreeMap<String, String>) -> Self {
+        self.specs = Some(specs);
+        self
+    }
+
+    /// Sets attributes to be emitted above the functions, e.g. `test` for `#[test]`.
+    pub fn with_attributes(mut self, attributes: &'a BTreeMap<String, Vec<String>>) -> Self {
+        self.attributes = Some(attributes);
+        self
+    }
+
+    /// Sets new names of the module structs and functions keyed by their original names.
+    /// The new names are applied to the definitions and to every reference to them.
+    /// Disassembly fails if the renamed module can't be compiled back.
+    pub fn with_renames(mut self, renames: &'a BTreeMap<String, String>) -> Self {
+        self.renames = Some(renames);
+        self
+    }
+
+    /// Returns the output name of the module struct or function.
+    fn rename(&self, name: &str) -> String {
+        self.renames
+            .and_then(|renames| renames.get(name))
+            .map_or_else(|| name.to_owned(), |name| name.to_owned())
+    }
+
+    /// Sets strict mode.
+    /// In strict mode disassembly fails if the module can't be reproduced without losses.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets compact mode.
+    /// In compact mode indentation and line breaks are collapsed into single spaces.
+    pub fn with_compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Sets whether the last struct field is followed by a comma. Enabled by default.
+    pub fn with_trailing_comma(mut self, trailing_comma: bool) -> Self {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+
+    /// Sets whether struct field names are padded so that the field types line up. Disabled by default.
+    pub fn with_aligned_fields(mut self, align_fields: bool) -> Self {
+        self.align_fields = align_fields;
+        self
+    }
+
+    /// Sets the maximum size of the bytecode in bytes.
+    /// Larger modules are rejected before deserialization.
+    pub fn with_max_bytecode_size(mut self, max_bytecode_size: usize) -> Self {
+        self.max_bytecode_size = Some(max_bytecode_size);
+        self
+    }
+
+    /// Sets the maximum numbers of struct and function definitions.
+    /// Modules with more definitions are rejected before disassembly.
+    pub fn with_max_definitions(mut self, max_structs: usize, max_functions: usize) -> Self {
+        self.max_structs = Some(max_structs);
+        self.max_functions = Some(max_functions);
+        self
+    }
+
+    /// Sets a banner to be emitted as line comments before the module.
+    pub fn with_header(mut self, header: &'a str) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Sets the source map of the module.
+    /// Struct and function type parameters and function parameters take their original names
+    /// from the map instead of the synthesized ones.
+    /// Names which are not valid identifiers or collide with other names of the module are skipped.
+    pub fn with_source_map<L: Clone + Eq>(mut self, source_map: &ModuleSourceMap<L>) -> Self {
+        let names = |names: &[(String, L)]| {
+            names
+                .iter()
+                .map(|(name, _)| name.to_owned())
+                .collect::<Vec<_>>()
+        };
+        self.source_names = Some(SourceNames {
+            structs: source_map
+                .struct_map
+                .iter()
+                .map(|(index, def)| {
+                    (
+                        *index as usize,
+                        DefinitionNames {
+                            type_params: names(&def.type_parameters),
+                            locals: vec![],
+                        },
+                    )
+                })
+                .collect(),
+            functions: source_map
+                .function_map
+                .iter()
+                .map(|(index, function)| {
+                    (
+                        *index as usize,
+                        DefinitionNames {
+                            type_params: names(&function.type_parameters),
+                            locals: names(&function.locals),
+                        },
+                    )
+                })
+                .collect(),
+        });
+        self
+    }
+
+    /// Sets visibility comments mode.
+    /// In this mode every struct and function is preceded by a `// public` or `// private` comment.
+    /// Struct types are visible to all modules, so structs are always public.
+    pub fn with_visibility_comments(mut self, visibility_comments: bool) -> Self {
+        self.visibility_comments = visibility_comments;
+        self
+    }
+
+    /// Sets the number of spaces per indentation level. Defaults to 4.
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets bytecode listing mode.
+    /// In this mode the function bodies start with the instructions of the function,
+    /// one `// L<offset>: <mnemonic> <operand>` comment per instruction.
+    pub fn with_bytecode_listing(mut self, bytecode_listing: bool) -> Self {
+        self.bytecode_listing = bytecode_listing;
+        self
+    }
+
+    /// Sets handle indices mode.
+    /// In this mode every struct and function is preceded by a comment with the index of its handle.
+    pub fn with_handle_indices(mut self, handle_indices: bool) -> Self {
+        self.handle_indices = handle_indices;
+        self
+    }
 }
 
 impl<'a> Default for Config<'a> {
@@ -53,13 +272,290 @@ pub fn module_signature_with_configuration(
     bytecode: &[u8],
     config: Config,
 ) -> Result<ModuleSignature> {
+    check_limit("Bytecode size", bytecode.len(), config.max_bytecode_size)?;
+    let module = CompiledModule::deserialize(&bytecode)?;
+    check_limit(
+        "Number of structs",
+        module.as_inner().struct_defs.len(),
+        config.max_structs,
+    )?;
+    check_limit(
+        "Number of functions",
+        module.as_inner().function_defs.len(),
+        config.max_functions,
+    )?;
+    if let Some(renames) = config.renames {
+        check_renames(&module.as_inner(), renames)?;
+    }
+    if config.strict {
+        check_lossless(&module.as_inner(), &config)?;
+    }
+
+    Ok(extract_signature(module.as_inner(), config))
+}
+
+fn check_limit(name: &str, value: usize, limit: Option<usize>) -> Result<()> {
+    match limit {
+        Some(limit) if value > limit => bail!("{} {} exceeds the limit of {}.", name, value, limit),
+        _ => Ok(()),
+    }
+}
+
+/// Disassembles the module and extracts its dependencies, deserializing the bytecode once.
+pub fn disasm_and_deps(bytecode: &[u8]) -> Result<(String, HashSet<ModuleId>)> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let signature = extract_signature(module.as_inner(), Default::default()).to_string();
+
+    let mut extractor = BytecodeUses::default();
+    extractor.extract(module)?;
+    Ok((signature, extractor.imports()))
+}
+
+/// Disassembles only the module functions which names match the predicate.
+/// Only the module structs the functions refer to are emitted,
+/// imports are limited to the ones used by the functions and the structs.
+pub fn disasm_functions_matching<P: Fn(&str) -> bool>(
+    bytecode: &[u8],
+    predicate: P,
+) -> Result<String> {
     let module = CompiledModule::deserialize(&bytecode)?;
-    let id = module.self_id();
+    let module = module.as_inner();
+    let generic_prefix = IdentifierAllocator::new(module).fresh_prefix(GENERIC_PREFIX);
+    let mut config = Config::new(PHANTOM_RESOURCE_NAME, &generic_prefix, false);
+    config.function_filter = Some(&predicate);
+
+    let mut imports = Imports::new();
+    let functions = extract_functions(module, &config, &mut imports);
+    let referenced = referenced_structs(
+        module,
+        module.function_defs.iter().filter(|def| {
+            let handler = &module.function_handles[def.function.0 as usize];
+            predicate(module.identifiers[handler.name.0 as usize].as_str())
+        }),
+    );
+    let mut structs = Structs {
+        structs: referenced
+            .into_iter()
+            .map(|index| extract_struct(module, &module.struct_defs[index], &config, &mut imports))
+            .collect(),
+        errors: vec![],
+        indent_size: config.indent,
+    };
+    push_phantom_resource(&mut structs, &functions, &config);
+    let handle = &module.module_handles[0];
+    let signature = ModuleSignature {
+        id: ModuleId::new(
+            module.address_identifiers[handle.address.0 as usize],
+            module.identifiers[handle.name.0 as usize].clone(),
+        ),
+        structs,
+        functions,
+        imports,
+        compact: false,
+        header: None,
+    };
+    Ok(signature.to_string())
+}
+
+/// Returns the definition indices of the module structs which the functions refer to
+/// in their signatures and acquires lists, along with the structs their fields refer to.
+fn referenced_structs<'a>(
+    module: &CompiledModuleMut,
+    functions: impl Iterator<Item = &'a FunctionDefinition>,
+) -> BTreeSet<usize> {
+    let definitions = module
+        .struct_defs
+        .iter()
+        .enumerate()
+        .map(|(index, def)| (def.struct_handle.0, index))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut handles = vec![];
+    let mut pending = vec![];
+    for def in functions {
+        let handler = &module.function_handles[def.function.0 as usize];
+        let params = &module.signatures[handler.parameters.0 as usize].0;
+        let ret = &module.signatures[handler.return_.0 as usize].0;
+        for token in params.iter().chain(ret) {
+            struct_handles(token, &mut handles);
+        }
+        pending.extend(
+            def.acquires_global_resources
+                .iter()
+                .map(|index| index.0 as usize),
+        );
+    }
+
+    let mut referenced = BTreeSet::new();
+    loop {
+        pending.extend(
+            handles
+                .drain(..)
+                .filter_map(|handle| definitions.get(&handle.0).copied()),
+        );
+        let index = match pending.pop() {
+            Some(index) => index,
+            None => break,
+        };
+        if !referenced.insert(index) {
+            continue;
+        }
+        if let StructFieldInformation::Declared(fields) =
+            &module.struct_defs[index].field_information
+        {
+            for field in fields {
+                struct_handles(&field.signature.0, &mut handles);
+            }
+        }
+    }
+    referenced
+}
+
+/// Collects the handles of the structs the type refers to.
+fn struct_handles(token: &SignatureToken, handles: &mut Vec<StructHandleIndex>) {
+    match token {
+        SignatureToken::Vector(token)
+        | SignatureToken::Reference(token)
+        | SignatureToken::MutableReference(token) => struct_handles(token, handles),
+        SignatureToken::Struct(index) => handles.push(*index),
+        SignatureToken::StructInstantiation(index, typed) => {
+            handles.push(*index);
+            for token in typed {
+                struct_handles(token, handles);
+            }
+        }
+        SignatureToken::U8
+        | SignatureToken::Bool
+        | SignatureToken::U64
+        | SignatureToken::U128
+        | SignatureToken::Address
+        | SignatureToken::Signer
+        | SignatureToken::TypeParameter(_) => {}
+    }
+}
+
+/// Disassembles the module skipping the structs which can't be decoded.
+/// Each skipped struct is replaced with a comment. The decoding errors are returned alongside the signature.
+pub fn module_signature_partial(bytecode: &[u8]) -> Result<(ModuleSignature, Vec<String>)> {
+    let module = CompiledModuleMut::deserialize_no_check_bounds(&bytecode)?;
+    check_self_handle(&module)?;
+    for def in &module.function_defs {
+        check_function(&module, def)?;
+    }
+
+    let signature = extract_signature(&module, Default::default());
+    let errors = signature.structs.errors.clone();
+    Ok((signature, errors))
+}
+
+/// Checks whether the disassembled module can be compiled back without running the compiler.
+/// Verifies that all referenced tables entries exist, definitions have unique names
+/// and no struct takes the phantom resource name.
+pub fn validate_disassembly(bytecode: &[u8]) -> Result<DisassemblyReport> {
+    let module = CompiledModuleMut::deserialize_no_check_bounds(&bytecode)?;
+    let mut issues = vec![];
+    if let Err(err) = check_self_handle(&module) {
+        issues.push(format!("module handle: {}", err));
+    }
+
+    let mut struct_names = vec![];
+    for (i, def) in module.struct_defs.iter().enumerate() {
+        match check_struct(&module, def) {
+            Ok(()) => {
+                let handler = &module.struct_handles[def.struct_handle.0 as usize];
+                struct_names.push(module.identifiers[handler.name.0 as usize].as_str());
+            }
+            Err(err) => issues.push(format!("struct {}: {}", i, err)),
+        }
+    }
+
+    let mut function_names = vec![];
+    for (i, def) in module.function_defs.iter().enumerate() {
+        match check_function(&module, def) {
+            Ok(()) => {
+                let handler = &module.function_handles[def.function.0 as usize];
+                function_names.push(module.identifiers[handler.name.0 as usize].as_str());
+            }
+            Err(err) => issues.push(format!("function {}: {}", i, err)),
+        }
+    }
+
+    if struct_names.contains(&PHANTOM_RESOURCE_NAME) {
+        issues.push(format!(
+            "struct name '{}' is reserved for the phantom resource",
+            PHANTOM_RESOURCE_NAME
+        ));
+    }
+    let mut seen = BTreeSet::new();
+    for name in struct_names {
+        if !seen.insert(name) {
+            issues.push(format!("duplicate struct name '{}'", name));
+        }
+    }
+    let mut seen = BTreeSet::new();
+    for name in function_names {
+        if !seen.insert(name) {
+            issues.push(format!("duplicate function name '{}'", name));
+        }
+    }
+
+    Ok(DisassemblyReport { issues })
+}
+
+/// Potential round-trip issues of the disassembled module.
+#[derive(Debug)]
+pub struct DisassemblyReport {
+    issues: Vec<String>,
+}
+
+impl DisassemblyReport {
+    /// Returns true if no issues were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the found issues.
+    pub fn issues(&self) -> &[String] {
+        &self.issues
+    }
+}
+
+fn extract_signature(module: &CompiledModuleMut, config: Config) -> ModuleSignature {
+    let handle = &module.module_handles[0];
+    let id = ModuleId::new(
+        module.address_identifiers[handle.address.0 as usize],
+        module.identifiers[handle.name.0 as usize].clone(),
+    );
+
+    let generic_prefix = if config.exact_generic_prefix {
+        config.generic_prefix.to_owned()
+    } else {
+        IdentifierAllocator::new(module).fresh_prefix(config.generic_prefix)
+    };
+    let config = Config {
+        generic_prefix: &generic_prefix,
+        ..config
+    };
 
     let mut imports = Imports::new();
-    let functions = extract_functions(&module.as_inner(), &config, &mut imports);
+    imports.indent_size = config.indent;
+    let functions = extract_functions(module, &config, &mut imports);
+
+    let mut structs = extract_structs(module, &config, &mut imports);
+    push_phantom_resource(&mut structs, &functions, &config);
+
+    ModuleSignature {
+        id,
+        structs,
+        functions,
+        imports,
+        compact: config.compact,
+        header: config.header.map(str::to_owned),
+    }
+}
 
-    let mut structs = extract_structs(&module.as_inner(), &config, &mut imports);
+/// Adds the phantom resource if the function bodies borrow generic resources.
+fn push_phantom_resource(structs: &mut Structs, functions: &Functions, config: &Config) {
     if !config.only_interface
         && functions.has_acursors()
         && !structs.contains(config.phantom_resource_name)
@@ -69,647 +565,5250 @@ pub fn module_signature_with_configuration(
             is_native: false,
             name: config.phantom_resource_name.to_owned(),
             type_params: Default::default(),
-            indent_size: 4,
+            handle_index: None,
+            visibility: None,
+            duplicate: false,
+            indent_size: config.indent,
             fields: Params {
                 fields: vec![Field {
                     name: "dummy_field".to_string(),
-                    f_type: "bool".to_string(),
+                    f_type: FType::Bool,
                 }],
-                indent_size: 8,
+                indent_size: config.indent * 2,
                 is_struct_field: true,
+                trailing_comma: config.trailing_comma,
+                align: false,
             },
         });
     }
-
-    Ok(ModuleSignature {
-        id,
-        structs,
-        functions,
-        imports,
-    })
 }
 
-fn extract_structs(module: &CompiledModuleMut, config: &Config, imports: &mut Imports) -> Structs {
-    let structs = module
-        .struct_defs
+/// Disassembles each module of the package into `out_dir/<address>/<name>.move`.
+/// Returns the written path or the error for every module in the same order.
+pub fn write_module_signatures(modules: &[Vec<u8>], out_dir: &Path) -> Vec<Result<PathBuf>> {
+    modules
         .iter()
-        .map(|def| {
-            let handler = &module.struct_handles[def.struct_handle.0 as usize];
-            let name = module.identifiers[handler.name.0 as usize].to_string();
+        .map(|bytecode| {
+            let signature = module_signature(bytecode)?;
+            let id = signature.self_id();
+            let dir = out_dir.join(id.address().to_string());
+            fs::create_dir_all(&dir)?;
 
-            Struct {
-                is_nominal_resource: handler.is_nominal_resource,
-                is_native: def.field_information == StructFieldInformation::Native,
-                name,
-                type_params: extract_type_params(&handler.type_parameters, config),
-                indent_size: 4,
-                fields: Params {
-                    fields: extract_fields(module, &def.field_information, config, imports),
-                    indent_size: 8,
-                    is_struct_field: true,
-                },
-            }
+            let path = dir.join(format!("{}.move", id.name()));
+            let mut file = io::BufWriter::new(fs::File::create(&path)?);
+            signature.write_to(&mut file)?;
+            io::Write::flush(&mut file)?;
+            Ok(path)
         })
-        .collect();
-
-    Structs { structs }
+        .collect()
 }
 
-fn extract_type_params(params: &[Kind], config: &Config) -> TypeParams {
-    TypeParams {
-        params: params
-            .iter()
-            .enumerate()
-            .map(|(i, kind)| TypeParam {
-                name: format!("{}{}", config.generic_prefix, i + 1),
-                kind: kind.to_owned(),
-            })
-            .collect(),
+/// Returns the hash of the module structure: struct names and fields and function signatures.
+/// Formatting and import aliases don't affect the fingerprint.
+pub fn ast_fingerprint(bytecode: &[u8]) -> Result<u64> {
+    let config = Config::new(PHANTOM_RESOURCE_NAME, GENERIC_PREFIX, true);
+    let signature = module_signature_with_configuration(bytecode, config)?;
+
+    let mut hasher = XxHash64::default();
+    signature.id.hash(&mut hasher);
+    for s in &signature.structs.structs {
+        s.is_nominal_resource.hash(&mut hasher);
+        s.is_native.hash(&mut hasher);
+        s.name.hash(&mut hasher);
+        for param in &s.type_params.params {
+            param.kind.hash(&mut hasher);
+        }
+        for field in &s.fields.fields {
+            field.name.hash(&mut hasher);
+            field.f_type.hash(&mut hasher);
+        }
+    }
+    for f in &signature.functions.functions {
+        f.is_public.hash(&mut hasher);
+        f.is_native.hash(&mut hasher);
+        f.name.hash(&mut hasher);
+        for param in &f.type_params.params {
+            param.kind.hash(&mut hasher);
+        }
+        for param in &f.params.fields {
+            param.f_type.hash(&mut hasher);
+        }
+        f.ret.ret.hash(&mut hasher);
+        f.acquires.inner.hash(&mut hasher);
     }
+    Ok(hasher.finish())
 }
 
-fn extract_fields(
-    module: &CompiledModuleMut,
-    info: &StructFieldInformation,
-    config: &Config,
-    imports: &mut Imports,
-) -> Vec<Field> {
-    if let StructFieldInformation::Declared(fields) = info {
-        fields
-            .iter()
-            .map(|def| Field {
-                name: module.identifiers[def.name.0 as usize].as_str().to_owned(),
-                f_type: extract_type_signature(module, &def.signature.0, config, imports),
-            })
-            .collect()
-    } else {
-        vec![]
-    }
+/// Returns the public interface of the module as JSON.
+///
+/// Schema:
+/// ```json
+/// {
+///   "address": "0x<address>",
+///   "name": "<module name>",
+///   "structs": [
+///     {
+///       "name": "<struct name>",
+///       "resource": true,
+///       "native": false,
+///       "type_params": ["all" | "copyable" | "resource"],
+///       "fields": [{ "name": "<field name>", "type": "<type>" }]
+///     }
+///   ],
+///   "functions": [
+///     {
+///       "name": "<function name>",
+///       "native": false,
+///       "type_params": ["all" | "copyable" | "resource"],
+///       "params": [{ "type": "<type>", "is_signer": false }],
+///       "returns": ["<type>"]
+///     }
+///   ]
+/// }
+/// ```
+/// Only public functions are listed. Struct types are fully qualified (`0x<address>::<module>::<name>`),
+/// type parameters are referred to as `T1`, `T2`, ... in order of declaration.
+/// `&signer` parameters are provided by the transaction senders rather than by arguments,
+/// so they are flagged with `is_signer`.
+pub fn public_abi_json(bytecode: &[u8]) -> Result<String> {
+    // Type parameters are named by position, no source is emitted so they can't collide.
+    let config = Config {
+        exact_generic_prefix: true,
+        ..Config::new(PHANTOM_RESOURCE_NAME, ABI_GENERIC_PREFIX, true)
+    };
+    let signature = module_signature_with_configuration(bytecode, config)?;
+
+    let mut abi = signature.abi();
+    abi.functions.retain(|f| f.public);
+    Ok(serde_json::to_string(&abi)?)
 }
 
-fn extract_params(
-    module: &CompiledModuleMut,
-    info: &Signature,
-    config: &Config,
-    imports: &mut Imports,
-) -> Vec<Field> {
-    info.0
-        .iter()
-        .map(|param| extract_type_signature(module, param, config, imports))
-        .enumerate()
-        .map(|(i, param)| Field {
-            name: format!("_arg_{}", i + 1),
-            f_type: param,
-        })
-        .collect()
+fn is_signer(f_type: &FType) -> bool {
+    match f_type {
+        FType::Signer => true,
+        FType::Ref(f_type) => matches!(**f_type, FType::Signer),
+        _ => false,
+    }
 }
 
-fn extract_return_value(
-    module: &CompiledModuleMut,
-    info: &Signature,
-    config: &Config,
-    imports: &mut Imports,
-) -> FuncResult {
-    FuncResult {
-        ret: info
-            .0
-            .iter()
-            .map(|param| extract_type_signature(module, param, config, imports))
-            .collect(),
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::All => "all",
+        Kind::Copyable => "copyable",
+        Kind::Resource => "resource",
     }
 }
 
-fn extract_type_signature(
-    module: &CompiledModuleMut,
-    signature: &SignatureToken,
-    config: &Config,
-    imports: &mut Imports,
-) -> String {
-    match signature {
-        SignatureToken::U8 => "u8".to_owned(),
-        SignatureToken::Bool => "bool".to_owned(),
-        SignatureToken::U64 => "u64".to_owned(),
-        SignatureToken::U128 => "u128".to_owned(),
-        SignatureToken::Address => "address".to_owned(),
-        SignatureToken::Vector(sign) => format!(
-            "vector<{}>",
-            extract_type_signature(module, sign.as_ref(), config, imports)
-        ),
-        SignatureToken::Struct(struct_index) => {
-            extract_strict_full_name(module, *struct_index, imports)
-        }
-        SignatureToken::StructInstantiation(struct_index, typed) => format!(
+/// Renders the type with fully qualified struct names.
+fn abi_type(f_type: &FType) -> String {
+    let struct_name =
+        |name: &FullStructName| format!("0x{}::{}::{}", name.address, name.module, name.name);
+    match f_type {
+        FType::Vec(f_type) => format!("vector<{}>", abi_type(f_type)),
+        FType::Struct(name) => struct_name(name),
+        FType::StructInst(name, params) => format!(
             "{}<{}>",
-            extract_strict_full_name(module, *struct_index, imports),
-            typed
-                .iter()
-                .map(|t| extract_type_signature(module, t, config, imports))
-                .collect::<Vec<_>>()
-                .join(", ")
-        ),
-        SignatureToken::Reference(sign) => format!(
-            "&{}",
-            extract_type_signature(module, sign.as_ref(), config, imports)
+            struct_name(name),
+            params.iter().map(abi_type).collect::<Vec<_>>().join(", ")
         ),
-        SignatureToken::MutableReference(sign) => format!(
-            "&mut {}",
-            extract_type_signature(module, sign.as_ref(), config, imports)
-        ),
-        SignatureToken::TypeParameter(index) => format!("{}{}", config.generic_prefix, index + 1),
-        SignatureToken::Signer => "signer".to_owned(),
+        FType::Ref(f_type) => format!("&{}", abi_type(f_type)),
+        FType::RefMut(f_type) => format!("&mut {}", abi_type(f_type)),
+        FType::Generic(_)
+        | FType::U8
+        | FType::U64
+        | FType::U128
+        | FType::Bool
+        | FType::Address
+        | FType::Signer => f_type.to_string(),
     }
 }
 
-fn extract_strict_full_name(
-    module: &CompiledModuleMut,
-    struct_index: StructHandleIndex,
-    imports: &mut Imports,
-) -> String {
-    let handler = &module.struct_handles[struct_index.0 as usize];
-    let type_name = module.identifiers[handler.name.0 as usize].as_str();
-    if handler.module.0 == 0 {
-        type_name.to_owned()
-    } else {
-        let module_handler = &module.module_handles[handler.module.0 as usize];
-        let module_name = module.identifiers[module_handler.name.0 as usize].as_str();
-        let address = &module.address_identifiers[module_handler.address.0 as usize];
-        let alias = imports.add(address, module_name);
-        format!("{}::{}", alias, type_name)
-    }
+/// Module metadata.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModuleAbi {
+    /// Module address in the `0x<address>` form.
+    pub address: String,
+    /// Module name.
+    pub name: String,
+    /// Structs in the declaration order.
+    pub structs: Vec<StructAbi>,
+    /// Functions in the declaration order.
+    pub functions: Vec<FunctionAbi>,
 }
 
-fn extract_functions(
-    module: &CompiledModuleMut,
-    config: &Config,
-    imports: &mut Imports,
-) -> Functions {
-    let functions = module
-        .function_defs
-        .iter()
-        .map(|def| {
-            let handler = &module.function_handles[def.function.0 as usize];
-            let name = module.identifiers[handler.name.0 as usize].to_string();
-            let signatures = &module.signatures[handler.parameters.0 as usize];
+/// Struct metadata.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StructAbi {
+    /// Struct name.
+    pub name: String,
+    /// True for the resource structs.
+    pub resource: bool,
+    /// True for the native structs.
+    pub native: bool,
+    /// Kinds of the type parameters.
+    pub type_params: Vec<&'static str>,
+    /// Struct fields.
+    pub fields: Vec<FieldAbi>,
+}
 
-            let (instructions, acquires) = if !def.is_native() {
-                let mut body = Vec::new();
-                let mut acquires = Vec::new();
+/// Struct field metadata.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldAbi {
+    /// Field name.
+    pub name: String,
+    /// Field type with fully qualified struct names.
+    #[serde(rename = "type")]
+    pub f_type: String,
+}
 
-                if !config.only_interface {
-                    for acquire in &def.acquires_global_resources {
-                        let struct_defs = &module.struct_defs[acquire.0 as usize];
-                        let handler = &module.struct_handles[struct_defs.struct_handle.0 as usize];
-                        let name = module.identifiers[handler.name.0 as usize].to_string();
+/// Function metadata.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionAbi {
+    /// Function name.
+    pub name: String,
+    /// True for the public functions.
+    #[serde(skip)]
+    pub public: bool,
+    /// True for the native functions.
+    pub native: bool,
+    /// Kinds of the type parameters.
+    pub type_params: Vec<&'static str>,
+    /// Function parameters.
+    pub params: Vec<ParamAbi>,
+    /// Types of the returned values.
+    pub returns: Vec<String>,
+}
 
-                        if handler.type_parameters.is_empty() {
-                            body.push(Instruction::Borrow(name.to_string()));
-                        } else {
-                            let params = handler
-                                .type_parameters
-                                .iter()
-                                .map(|param| match param {
-                                    Kind::Resource => config.phantom_resource_name.to_string(),
-                                    Kind::All | Kind::Copyable => "u64".to_string(),
-                                })
-                                .collect::<Vec<_>>()
-                                .join(", ");
-                            body.push(Instruction::Borrow(format!("{}<{}>", name, params)));
-                        }
+/// Function parameter metadata.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ParamAbi {
+    /// Parameter type with fully qualified struct names.
+    #[serde(rename = "type")]
+    pub f_type: String,
+    /// True for the `signer` and `&signer` parameters.
+    pub is_signer: bool,
+}
 
-                        acquires.push(name);
-                    }
+/// Returns the module outline: the module node with structs and their fields
+/// followed by functions and their parameters.
+pub fn module_outline(bytecode: &[u8]) -> Result<OutlineNode> {
+    let config = Config::new(PHANTOM_RESOURCE_NAME, GENERIC_PREFIX, true);
+    let signature = module_signature_with_configuration(bytecode, config)?;
 
-                    body.push(Instruction::Abort(1));
-                }
-                (body, acquires)
-            } else {
-                (vec![], vec![])
-            };
-            Function {
-                is_public: def.is_public(),
-                is_native: def.is_native(),
-                name,
-                type_params: extract_type_params(&handler.type_parameters, config),
-                params: Params {
-                    fields: extract_params(module, &signatures, config, imports),
-                    indent_size: 0,
-                    is_struct_field: false,
-                },
-                ret: extract_return_value(
-                    module,
-                    &module.signatures[handler.return_.0 as usize],
-                    config,
-                    imports,
-                ),
-                acquires: Acquires { inner: acquires },
-                indent_size: 4,
-                body: Block {
-                    instructions,
-                    indent_size: 4,
-                    instructions_indent_size: 8,
-                },
-            }
-        })
-        .collect();
-    Functions { functions }
+    let fields = |params: &Params, kind: OutlineKind| {
+        params
+            .fields
+            .iter()
+            .map(|field| OutlineNode::new(&field.name, kind, vec![]))
+            .collect::<Vec<_>>()
+    };
+
+    let structs = signature.structs.structs.iter().map(|s| {
+        OutlineNode::new(
+            &s.name,
+            OutlineKind::Struct,
+            fields(&s.fields, OutlineKind::Field),
+        )
+    });
+    let functions = signature.functions.functions.iter().map(|f| {
+        OutlineNode::new(
+            &f.name,
+            OutlineKind::Function,
+            fields(&f.params, OutlineKind::Param),
+        )
+    });
+
+    Ok(OutlineNode::new(
+        signature.id.name().as_str(),
+        OutlineKind::Module,
+        structs.chain(functions).collect(),
+    ))
 }
 
-enum Instruction {
-    Abort(u8),
-    Borrow(String),
+/// Disassembles the module and splits the output into classified tokens.
+/// Whitespace is not included, token offsets are byte offsets in the disassembled text.
+/// The tokens are lexed from the rendered text: only the primitive types are classified as types,
+/// struct and type parameter names are identifiers.
+pub fn disasm_tokens(bytecode: &[u8]) -> Result<Vec<Token>> {
+    Ok(tokenize(&module_signature(bytecode)?.to_string()))
 }
 
-impl Display for Instruction {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Instruction::Abort(code) => write!(f, "abort {}", code),
-            Instruction::Borrow(resources) => write!(f, "borrow_global<{}>(0x0);", resources),
+/// Disassembles the root module and all modules reachable from it.
+/// Modules are loaded from the bytecode source.
+pub fn reachable_module_signatures<S: BytecodeSource>(
+    source: &S,
+    root: &ModuleId,
+) -> Result<BTreeMap<ModuleId, String>> {
+    let mut signatures = BTreeMap::new();
+    let mut queue = vec![root.to_owned()];
+
+    while let Some(id) = queue.pop() {
+        if signatures.contains_key(&id) {
+            continue;
+        }
+
+        let bytecode = source.load(&id)?;
+        for dep in extract_from_bytecode(&bytecode)? {
+            if !signatures.contains_key(&dep) {
+                queue.push(dep);
+            }
         }
+        signatures.insert(id, module_signature(&bytecode)?.to_string());
     }
+
+    Ok(signatures)
 }
 
-struct Imports {
-    uses: BTreeMap<String, BTreeMap<AccountAddress, Option<String>>>,
-    indent_size: usize,
+/// Returns names of the module structs with the kinds of their type parameters.
+pub fn struct_type_params(bytecode: &[u8]) -> Result<Vec<(String, Vec<Kind>)>> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    Ok(module
+        .struct_defs
+        .iter()
+        .map(|def| {
+            let handler = &module.struct_handles[def.struct_handle.0 as usize];
+            (
+                module.identifiers[handler.name.0 as usize].to_string(),
+                handler.type_parameters.clone(),
+            )
+        })
+        .collect())
 }
 
-impl Imports {
-    pub fn new() -> Imports {
-        Imports {
-            uses: Default::default(),
-            indent_size: 4,
-        }
+/// Disassembles a single module struct preceded by the imports its fields need.
+pub fn disasm_struct(bytecode: &[u8], struct_name: &str) -> Result<String> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    let def = module
+        .struct_defs
+        .iter()
+        .find(|def| {
+            let handler = &module.struct_handles[def.struct_handle.0 as usize];
+            module.identifiers[handler.name.0 as usize].as_str() == struct_name
+        })
+        .ok_or_else(|| anyhow!("Struct '{}' not found.", struct_name))?;
+
+    let generic_prefix = IdentifierAllocator::new(module).fresh_prefix(GENERIC_PREFIX);
+    let config = Config::new(PHANTOM_RESOURCE_NAME, &generic_prefix, false);
+    let mut imports = Imports::new();
+    let mut def = extract_struct(module, def, &config, &mut imports);
+    imports.indent_size = 0;
+    def.indent_size = 0;
+    def.fields.indent_size = config.indent;
+
+    if imports.uses.is_empty() {
+        Ok(def.to_string())
+    } else {
+        Ok(format!("{}\n{}", imports, def))
     }
+}
 
-    pub fn add(&mut self, address: &AccountAddress, name: &str) -> String {
-        if let Some(ident) = self.uses.get_mut(name) {
-            if let Some(alias) = ident.get(address) {
-                if let Some(alias) = alias {
-                    alias.to_string()
-                } else {
-                    name.to_string()
-                }
-            } else {
-                let alias = format!("Other{}{}", name, ident.len());
-                ident.insert(*address, Some(alias.clone()));
-                alias
+/// Returns the functions of the module called by each module function.
+pub fn internal_call_graph(bytecode: &[u8]) -> Result<HashMap<String, HashSet<String>>> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    let function_name = |handle: FunctionHandleIndex| {
+        let handler = &module.function_handles[handle.0 as usize];
+        module.identifiers[handler.name.0 as usize].to_string()
+    };
+
+    Ok(module
+        .function_defs
+        .iter()
+        .map(|def| {
+            let callees = def
+                .code
+                .iter()
+                .flat_map(|code| code.code.iter())
+                .filter_map(|instruction| match instruction {
+                    Bytecode::Call(handle) => Some(*handle),
+                    Bytecode::CallGeneric(index) => {
+                        Some(module.function_instantiations[index.0 as usize].handle)
+                    }
+                    _ => None,
+                })
+                .filter(|handle| {
+                    is_self_module(module, module.function_handles[handle.0 as usize].module)
+                })
+                .map(function_name)
+                .collect();
+            (function_name(def.function), callees)
+        })
+        .collect())
+}
+
+/// Returns the global resources which the module function reads and writes.
+/// `borrow_global`, `exists` and `move_from` are reads, `move_to`, `move_to_sender`
+/// and `borrow_global_mut` are writes.
+pub fn resource_access(bytecode: &[u8], fn_name: &str) -> Result<ResourceAccess> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    let def = module
+        .function_defs
+        .iter()
+        .find(|def| {
+            let handler = &module.function_handles[def.function.0 as usize];
+            module.identifiers[handler.name.0 as usize].as_str() == fn_name
+        })
+        .ok_or_else(|| anyhow!("Function '{}' not found.", fn_name))?;
+
+    let struct_name = |index: StructDefinitionIndex| {
+        let def = &module.struct_defs[index.0 as usize];
+        let handler = &module.struct_handles[def.struct_handle.0 as usize];
+        module.identifiers[handler.name.0 as usize].to_string()
+    };
+    let instantiation = |index: u16| module.struct_def_instantiations[index as usize].def;
+
+    let mut access = ResourceAccess::default();
+    for instruction in def.code.iter().flat_map(|code| code.code.iter()) {
+        match instruction {
+            Bytecode::ImmBorrowGlobal(index)
+            | Bytecode::Exists(index)
+            | Bytecode::MoveFrom(index) => {
+                access.reads.insert(struct_name(*index));
             }
-        } else {
-            let mut alias_map = BTreeMap::new();
-            alias_map.insert(address.to_owned(), None);
-            self.uses.insert(name.to_owned(), alias_map);
-            name.to_owned()
+            Bytecode::ImmBorrowGlobalGeneric(index)
+            | Bytecode::ExistsGeneric(index)
+            | Bytecode::MoveFromGeneric(index) => {
+                access.reads.insert(struct_name(instantiation(index.0)));
+            }
+            Bytecode::MoveTo(index)
+            | Bytecode::MoveToSender(index)
+            | Bytecode::MutBorrowGlobal(index) => {
+                access.writes.insert(struct_name(*index));
+            }
+            Bytecode::MoveToGeneric(index)
+            | Bytecode::MoveToSenderGeneric(index)
+            | Bytecode::MutBorrowGlobalGeneric(index) => {
+                access.writes.insert(struct_name(instantiation(index.0)));
+            }
+            _ => {}
         }
     }
+    Ok(access)
 }
 
-impl Display for Imports {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for (ident, aliases) in &self.uses {
-            for (addr, alias) in aliases {
-                if let Some(alias) = alias {
-                    writeln!(
-                        f,
-                        "{:width$}use 0x{address}::{name} as {alias};",
-                        "",
-                        address = addr,
-                        name = ident,
-                        width = self.indent_size,
-                        alias = alias
-                    )?;
-                } else {
-                    writeln!(
-                        f,
-                        "{:width$}use 0x{address}::{name};",
-                        "",
-                        address = addr,
-                        name = ident,
-                        width = self.indent_size,
-                    )?;
+/// Global resources accessed by a function.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResourceAccess {
+    /// Names of the module resources which the function reads.
+    pub reads: BTreeSet<String>,
+    /// Names of the module resources which the function writes.
+    pub writes: BTreeSet<String>,
+}
+
+/// Returns the module structs which are emitted as events.
+/// A struct is considered an event if the module code passes it as the type argument
+/// to a function named `emit_event`.
+pub fn event_structs(bytecode: &[u8]) -> Result<Vec<Struct>> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+
+    let mut names = BTreeSet::new();
+    let calls = module
+        .function_defs
+        .iter()
+        .filter_map(|def| def.code.as_ref())
+        .flat_map(|code| code.code.iter())
+        .filter_map(|instruction| match instruction {
+            Bytecode::CallGeneric(index) => Some(&module.function_instantiations[index.0 as usize]),
+            _ => None,
+        });
+    for call in calls {
+        let handler = &module.function_handles[call.handle.0 as usize];
+        if module.identifiers[handler.name.0 as usize].as_str() != EMIT_EVENT_NAME {
+            continue;
+        }
+        for type_arg in &module.signatures[call.type_parameters.0 as usize].0 {
+            let index = match type_arg {
+                SignatureToken::Struct(index) | SignatureToken::StructInstantiation(index, _) => {
+                    index
                 }
+                _ => continue,
+            };
+            let handler = &module.struct_handles[index.0 as usize];
+            if is_self_module(module, handler.module) {
+                names.insert(module.identifiers[handler.name.0 as usize].as_str());
             }
         }
-        Ok(())
     }
+
+    let signature = module_signature(bytecode)?;
+    Ok(signature
+        .structs
+        .structs
+        .into_iter()
+        .filter(|s| names.contains(s.name.as_str()))
+        .collect())
 }
 
-struct TypeParam {
-    name: String,
-    kind: Kind,
+/// Returns the kinds of the module function type parameters.
+pub fn function_type_param_kinds(bytecode: &[u8], fn_name: &str) -> Result<Vec<Kind>> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    module
+        .function_defs
+        .iter()
+        .map(|def| &module.function_handles[def.function.0 as usize])
+        .find(|handler| module.identifiers[handler.name.0 as usize].as_str() == fn_name)
+        .map(|handler| handler.type_parameters.clone())
+        .ok_or_else(|| anyhow!("Function '{}' not found.", fn_name))
 }
 
-impl Display for TypeParam {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self.kind {
-            Kind::All => write!(f, "{}", self.name),
-            Kind::Resource => write!(f, "{}: resource", self.name),
-            Kind::Copyable => write!(f, "{}: copyable", self.name),
+/// Returns the storage size of the module struct.
+/// Vectors, type parameters, native structs and structs of other modules have no fixed size,
+/// so they only mark the size as variable.
+pub fn struct_storage_size(bytecode: &[u8], struct_name: &str) -> Result<StorageSize> {
+    let signature = module_signature(bytecode)?;
+    let mut size = StorageSize::default();
+    add_struct_size(
+        &signature.structs.structs,
+        struct_name,
+        &mut vec![],
+        &mut size,
+    )?;
+    Ok(size)
+}
+
+/// Storage size of a struct.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StorageSize {
+    /// Total size of the fixed size fields in bytes.
+    pub fixed: usize,
+    /// The struct has fields which size depends on the value.
+    pub variable: bool,
+}
+
+fn add_struct_size<'a>(
+    structs: &'a [Struct],
+    name: &str,
+    path: &mut Vec<&'a str>,
+    size: &mut StorageSize,
+) -> Result<()> {
+    let def = structs
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| anyhow!("Struct '{}' not found.", name))?;
+    if path.contains(&def.name.as_str()) {
+        bail!("Struct '{}' is recursive.", name);
+    }
+    if def.is_native {
+        size.variable = true;
+        return Ok(());
+    }
+
+    path.push(&def.name);
+    for field in &def.fields.fields {
+        add_type_size(structs, &field.f_type, path, size)?;
+    }
+    path.pop();
+    Ok(())
+}
+
+fn add_type_size<'a>(
+    structs: &'a [Struct],
+    f_type: &FType,
+    path: &mut Vec<&'a str>,
+    size: &mut StorageSize,
+) -> Result<()> {
+    match f_type {
+        FType::U8 | FType::Bool => size.fixed += 1,
+        FType::U64 => size.fixed += 8,
+        FType::U128 => size.fixed += 16,
+        FType::Address | FType::Signer => size.fixed += AccountAddress::LENGTH,
+        FType::Struct(name) | FType::StructInst(name, _) if name.import.is_none() => {
+            add_struct_size(structs, &name.name, path, size)?
         }
+        FType::Generic(_)
+        | FType::Vec(_)
+        | FType::Struct(_)
+        | FType::StructInst(_, _)
+        | FType::Ref(_)
+        | FType::RefMut(_) => size.variable = true,
     }
+    Ok(())
 }
 
-#[derive(Default)]
-struct TypeParams {
-    params: Vec<TypeParam>,
+/// Returns the decoded values of the module constant pool with their types.
+pub fn module_constants(bytecode: &[u8]) -> Result<Vec<(FType, ConstValue)>> {
+    let module = CompiledModule::deserialize(&bytecode)?;
+    let module = module.as_inner();
+    let config = Config::default();
+    let mut imports = Imports::new();
+
+    module
+        .constant_pool
+        .iter()
+        .map(|constant| {
+            let mut data = constant.data.as_slice();
+            let value = decode_constant(&constant.type_, &mut data)?;
+            if !data.is_empty() {
+                bail!(
+                    "Unexpected trailing bytes in the constant of type {:?}.",
+                    constant.type_
+                );
+            }
+            Ok((
+                extract_type_signature(module, &constant.type_, &config, &mut imports),
+                value,
+            ))
+        })
+        .collect()
 }
 
-impl Display for TypeParams {
+/// Decoded value of a module constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstValue {
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Bool(bool),
+    Address(AccountAddress),
+    /// Value of the `vector<u8>` type.
+    Bytes(Vec<u8>),
+    Vector(Vec<ConstValue>),
+}
+
+/// Renders the value with an explicit integer type suffix.
+/// Scalars and `vector<u8>` are rendered as Move literals. Other vectors have no literal syntax
+/// in this Move dialect and are rendered as a list of their elements, e.g. `vector[1u64, 2u64]`.
+impl Display for ConstValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if !self.params.is_empty() {
-            write!(
+        match self {
+            ConstValue::U8(value) => write!(f, "{}u8", value),
+            ConstValue::U64(value) => write!(f, "{}u64", value),
+            ConstValue::U128(value) => write!(f, "{}u128", value),
+            ConstValue::Bool(value) => write!(f, "{}", value),
+            ConstValue::Address(address) => write!(f, "0x{}", address),
+            ConstValue::Bytes(bytes) => write!(f, "x\"{}\"", hex::encode(bytes)),
+            ConstValue::Vector(values) => write!(
                 f,
-                "<{}>",
-                self.params
+                "vector[{}]",
+                values
                     .iter()
                     .map(ToString::to_string)
                     .collect::<Vec<_>>()
                     .join(", ")
-            )
-        } else {
-            Ok(())
+            ),
         }
     }
 }
 
-struct Field {
-    name: String,
-    f_type: String,
-}
+/// Decodes the LCS encoded constant value of the given type and advances `data` past it.
+fn decode_constant(type_: &SignatureToken, data: &mut &[u8]) -> Result<ConstValue> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if data.len() < len {
+            bail!("Unexpected end of the constant data.");
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
 
-impl Display for Field {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.name, self.f_type)
+    fn uleb128(data: &mut &[u8]) -> Result<usize> {
+        let mut value = 0usize;
+        for shift in (0..32).step_by(7) {
+            let byte = take(data, 1)?[0];
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("Invalid vector length in the constant data.")
     }
+
+    Ok(match type_ {
+        SignatureToken::U8 => ConstValue::U8(take(data, 1)?[0]),
+        SignatureToken::U64 => {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(take(data, 8)?);
+            ConstValue::U64(u64::from_le_bytes(buf))
+        }
+        SignatureToken::U128 => {
+            let mut buf = [0; 16];
+            buf.copy_from_slice(take(data, 16)?);
+            ConstValue::U128(u128::from_le_bytes(buf))
+        }
+        SignatureToken::Bool => match take(data, 1)?[0] {
+            0 => ConstValue::Bool(false),
+            1 => ConstValue::Bool(true),
+            value => bail!("Invalid bool value {} in the constant data.", value),
+        },
+        SignatureToken::Address => ConstValue::Address(AccountAddress::try_from(take(
+            data,
+            AccountAddress::LENGTH,
+        )?)?),
+        SignatureToken::Vector(inner) => {
+            let len = uleb128(data)?;
+            if **inner == SignatureToken::U8 {
+                ConstValue::Bytes(take(data, len)?.to_vec())
+            } else {
+                ConstValue::Vector(
+                    (0..len)
+                        .map(|_| decode_constant(inner, data))
+                        .collect::<Result<_>>()?,
+                )
+            }
+        }
+        _ => bail!("Constants of type {:?} are not supported.", type_),
+    })
 }
 
-#[derive(Default)]
-struct Params {
-    fields: Vec<Field>,
-    indent_size: usize,
-    is_struct_field: bool,
+/// Returns types of the script entry function arguments.
+pub fn script_arg_types(bytecode: &[u8]) -> Result<Vec<FType>> {
+    let script = CompiledScript::deserialize(&bytecode)?;
+    let parameters = script.as_inner().parameters;
+    let (_, module) = script.into_module();
+    let module = module.into_inner();
+
+    let generic_prefix = IdentifierAllocator::new(&module).fresh_prefix(GENERIC_PREFIX);
+    let config = Config::new(PHANTOM_RESOURCE_NAME, &generic_prefix, false);
+    let mut imports = Imports::new();
+    Ok(module.signatures[parameters.0 as usize]
+        .0
+        .iter()
+        .map(|param| extract_type_signature(&module, param, &config, &mut imports))
+        .collect())
 }
 
-impl Display for Params {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for (i, field) in self.fields.iter().enumerate() {
-            write!(
-                f,
-                "{s:width$}{field}{end}",
-                field = field,
-                s = "",
-                width = self.indent_size,
-                end = if self.is_struct_field {
-                    ",\n"
-                } else if i == self.fields.len() - 1 {
-                    ""
-                } else {
-                    ", "
-                }
-            )?;
+/// Checks that the arguments match the script entry function parameters.
+/// Leading `&signer` parameters are provided by the transaction senders and take no arguments.
+pub fn validate_script_args(bytecode: &[u8], args: &[TransactionArgument]) -> Result<()> {
+    let params = script_arg_types(bytecode)?
+        .into_iter()
+        .skip_while(|param| match param {
+            FType::Ref(f_type) => matches!(**f_type, FType::Signer),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    if params.len() != args.len() {
+        bail!(
+            "Expected {} script arguments, got {}.",
+            params.len(),
+            args.len()
+        );
+    }
+
+    for (i, (param, arg)) in params.iter().zip(args).enumerate() {
+        let matches = match (param, arg) {
+            (FType::U8, TransactionArgument::U8(_))
+            | (FType::U64, TransactionArgument::U64(_))
+            | (FType::U128, TransactionArgument::U128(_))
+            | (FType::Bool, TransactionArgument::Bool(_))
+            | (FType::Address, TransactionArgument::Address(_)) => true,
+            (FType::Vec(f_type), TransactionArgument::U8Vector(_)) => {
+                matches!(**f_type, FType::U8)
+            }
+            _ => false,
+        };
+        if !matches {
+            bail!(
+                "Script argument {} has type {}, expected {}.",
+                i,
+                argument_type(arg),
+                param
+            );
         }
+    }
+    Ok(())
+}
 
-        Ok(())
+fn argument_type(arg: &TransactionArgument) -> &'static str {
+    match arg {
+        TransactionArgument::U8(_) => "u8",
+        TransactionArgument::U64(_) => "u64",
+        TransactionArgument::U128(_) => "u128",
+        TransactionArgument::Bool(_) => "bool",
+        TransactionArgument::Address(_) => "address",
+        TransactionArgument::U8Vector(_) => "vector<u8>",
     }
 }
 
-struct Struct {
-    is_nominal_resource: bool,
-    is_native: bool,
-    name: String,
-    type_params: TypeParams,
-    indent_size: usize,
-    fields: Params,
+/// Returns the number of the script entry function type parameters.
+pub fn script_type_params_count(bytecode: &[u8]) -> Result<usize> {
+    let script = CompiledScript::deserialize(&bytecode)?;
+    Ok(script.as_inner().type_parameters.len())
 }
 
-impl Display for Struct {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let nominal_name = if self.is_nominal_resource {
-            "resource struct"
-        } else if self.is_native {
-            "native struct"
-        } else {
-            "struct"
-        };
+/// Disassembles the script.
+/// Only the entry function signature is restored, its body is replaced with `abort`.
+pub fn script_signature(bytecode: &[u8]) -> Result<ScriptSignature> {
+    let script = CompiledScript::deserialize(&bytecode)?;
+    Ok(extract_script_signature(script))
+}
 
-        if self.is_native {
-            writeln!(
-                f,
-                "{s:width$}{nominal_name} {name}{params};",
-                s = "",
-                width = self.indent_size,
-                nominal_name = nominal_name,
-                name = self.name,
-                params = self.type_params,
-            )
-        } else {
+fn extract_script_signature(script: CompiledScript) -> ScriptSignature {
+    let (_, module) = script.into_module();
+    let module = module.into_inner();
+
+    let generic_prefix = IdentifierAllocator::new(&module).fresh_prefix(GENERIC_PREFIX);
+    let config = Config::new(PHANTOM_RESOURCE_NAME, &generic_prefix, false);
+    let mut imports = Imports::new();
+    let mut functions = extract_functions(&module, &config, &mut imports);
+    for function in &mut functions.functions {
+        // Script functions are declared without the visibility modifier.
+        function.is_public = false;
+    }
+    ScriptSignature { functions, imports }
+}
+
+/// Disassembles a script or a module.
+/// The bytecode is decoded as a script first and as a module if that fails.
+/// If neither decoding succeeds, the error reports both failures.
+pub fn disasm(bytecode: &[u8]) -> Result<String> {
+    let script_err = match CompiledScript::deserialize(bytecode) {
+        Ok(script) => return Ok(extract_script_signature(script).to_string()),
+        Err(err) => err,
+    };
+    match module_signature(bytecode) {
+        Ok(signature) => Ok(signature.to_string()),
+        Err(module_err) => bail!(
+            "Bytecode is neither a script ({}) nor a module ({}).",
+            script_err,
+            module_err
+        ),
+    }
+}
+
+/// Checks that the renamed module can be compiled back.
+/// New names must be valid identifiers distinct from each other and from the names which are kept.
+/// A name shared by a struct and a function can't be renamed, the rename would apply to both.
+fn check_renames(module: &CompiledModuleMut, renames: &BTreeMap<String, String>) -> Result<()> {
+    let name = |index: IdentifierIndex| module.identifiers[index.0 as usize].as_str();
+    let structs = module
+        .struct_defs
+        .iter()
+        .map(|def| name(module.struct_handles[def.struct_handle.0 as usize].name))
+        .collect::<BTreeSet<_>>();
+    let functions = module
+        .function_defs
+        .iter()
+        .map(|def| name(module.function_handles[def.function.0 as usize].name))
+        .collect::<BTreeSet<_>>();
+
+    let mut targets = BTreeSet::new();
+    for (old, new) in renames {
+        if structs.contains(old.as_str()) && functions.contains(old.as_str()) {
+            bail!(
+                "Rename of '{}' is ambiguous: the module has a struct and a function with this name.",
+                old
+            );
+        }
+        if !Identifier::is_valid(new) {
+            bail!("New name '{}' of '{}' is not a valid identifier.", new, old);
+        }
+        if !targets.insert(new.as_str()) {
+            bail!("New name '{}' is given to more than one definition.", new);
+        }
+        let kept = structs.contains(new.as_str()) || functions.contains(new.as_str());
+        if kept && !renames.contains_key(new) {
+            bail!(
+                "New name '{}' of '{}' is already taken by the module.",
+                new,
+                old
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the module can be disassembled without losses.
+fn check_lossless(module: &CompiledModuleMut, config: &Config) -> Result<()> {
+    if !module.constant_pool.is_empty() {
+        bail!("Module constants can't be disassembled.");
+    }
+
+    for def in &module.struct_defs {
+        let handler = &module.struct_handles[def.struct_handle.0 as usize];
+        let name = module.identifiers[handler.name.0 as usize].as_str();
+        if name == config.phantom_resource_name {
+            bail!(
+                "Struct name '{}' is reserved for the phantom resource.",
+                name
+            );
+        }
+
+        if let StructFieldInformation::Declared(fields) = &def.field_information {
+            for field in fields {
+                if contains_reference(&field.signature.0) {
+                    bail!(
+                        "Field '{}' of the struct '{}' has a reference type.",
+                        module.identifiers[field.name.0 as usize],
+                        name
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(name) = first_duplicate(module.struct_defs.iter().map(|def| {
+        let handler = &module.struct_handles[def.struct_handle.0 as usize];
+        module.identifiers[handler.name.0 as usize].as_str()
+    })) {
+        bail!("Duplicate struct name '{}'.", name);
+    }
+
+    if let Some(name) = first_duplicate(module.function_defs.iter().map(|def| {
+        let handler = &module.function_handles[def.function.0 as usize];
+        module.identifiers[handler.name.0 as usize].as_str()
+    })) {
+        bail!("Duplicate function name '{}'.", name);
+    }
+
+    for def in &module.function_defs {
+        let handler = &module.function_handles[def.function.0 as usize];
+        let signatures = module.signatures[handler.parameters.0 as usize]
+            .0
+            .iter()
+            .chain(&module.signatures[handler.return_.0 as usize].0);
+        for signature in signatures {
+            let nested = match signature {
+                SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+                    contains_reference(inner)
+                }
+                _ => contains_reference(signature),
+            };
+            if nested {
+                bail!(
+                    "Function '{}' has a nested reference type.",
+                    module.identifiers[handler.name.0 as usize]
+                );
+            }
+        }
+    }
+
+    for def in &module.function_defs {
+        if !def.is_native() {
+            let handler = &module.function_handles[def.function.0 as usize];
+            bail!(
+                "Body of the function '{}' can't be disassembled.",
+                module.identifiers[handler.name.0 as usize]
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the type is or contains a reference.
+fn contains_reference(signature: &SignatureToken) -> bool {
+    match signature {
+        SignatureToken::Reference(_) | SignatureToken::MutableReference(_) => true,
+        SignatureToken::Vector(inner) => contains_reference(inner),
+        SignatureToken::StructInstantiation(_, typed) => typed.iter().any(contains_reference),
+        _ => false,
+    }
+}
+
+/// Returns the first name that occurs more than once.
+fn first_duplicate<'a>(mut names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let mut seen = BTreeSet::new();
+    names.find(|name| !seen.insert(*name))
+}
+
+/// Structs are emitted in the order of their definitions.
+/// The compiler defines structs in alphabetical order of their names rather than in the source order,
+/// so the disassembled module keeps the same order after recompilation.
+fn extract_structs(module: &CompiledModuleMut, config: &Config, imports: &mut Imports) -> Structs {
+    let mut names = BTreeSet::new();
+    let reserved = config
+        .source_names
+        .as_ref()
+        .map(|_| reserved_names(module, config));
+    let mut errors = vec![];
+    let structs = module
+        .struct_defs
+        .iter()
+        .enumerate()
+        .filter(|(i, def)| match check_struct(module, def) {
+            Ok(()) => true,
+            Err(err) => {
+                errors.push(format!("error decoding struct {}: {}", i, err));
+                false
+            }
+        })
+        .map(|(index, def)| {
+            let mut def = extract_struct(module, def, config, imports);
+            def.duplicate = !names.insert(def.name.clone());
+            let source_names = config
+                .source_names
+                .as_ref()
+                .and_then(|source_names| source_names.structs.get(&index));
+            if let (Some(source_names), Some(reserved)) = (source_names, &reserved) {
+                source_names.apply_to_struct(&mut def, config.generic_prefix, reserved);
+            }
+            def
+        })
+        .collect();
+
+    Structs {
+        structs,
+        errors,
+        indent_size: config.indent,
+    }
+}
+
+fn extract_struct(
+    module: &CompiledModuleMut,
+    def: &StructDefinition,
+    config: &Config,
+    imports: &mut Imports,
+) -> Struct {
+    let handler = &module.struct_handles[def.struct_handle.0 as usize];
+    Struct {
+        is_nominal_resource: handler.is_nominal_resource,
+        is_native: def.field_information == StructFieldInformation::Native,
+        name: config.rename(module.identifiers[handler.name.0 as usize].as_str()),
+        type_params: extract_type_params(&handler.type_parameters, config),
+        handle_index: handle_index(def.struct_handle.0, config),
+        visibility: visibility(true, config),
+        duplicate: false,
+        indent_size: config.indent,
+        fields: Params {
+            fields: extract_fields(module, &def.field_information, config, imports),
+            indent_size: config.indent * 2,
+            is_struct_field: true,
+            trailing_comma: config.trailing_comma,
+            align: config.align_fields,
+        },
+    }
+}
+
+/// Returns the table entry or the error if the index is out of bounds.
+fn table_entry<'a, T>(table: &'a [T], index: u16, name: &str) -> Result<&'a T> {
+    table
+        .get(index as usize)
+        .ok_or_else(|| anyhow!("{} index {} is out of bounds", name, index))
+}
+
+/// Checks that the self module handle can be decoded.
+fn check_self_handle(module: &CompiledModuleMut) -> Result<()> {
+    let handle = table_entry(&module.module_handles, 0, "module handle")?;
+    table_entry(&module.identifiers, handle.name.0, "identifier")?;
+    table_entry(&module.address_identifiers, handle.address.0, "address")?;
+    Ok(())
+}
+
+/// Checks that the struct definition can be decoded.
+fn check_struct(module: &CompiledModuleMut, def: &StructDefinition) -> Result<()> {
+    check_struct_handle(module, def.struct_handle)?;
+    if let StructFieldInformation::Declared(fields) = &def.field_information {
+        for field in fields {
+            table_entry(&module.identifiers, field.name.0, "identifier")?;
+            check_type_signature(module, &field.signature.0)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the function signature can be decoded.
+fn check_function(module: &CompiledModuleMut, def: &FunctionDefinition) -> Result<()> {
+    let handle = table_entry(&module.function_handles, def.function.0, "function handle")?;
+    table_entry(&module.identifiers, handle.name.0, "identifier")?;
+    for index in &[handle.parameters, handle.return_] {
+        for token in &table_entry(&module.signatures, index.0, "signature")?.0 {
+            check_type_signature(module, token)?;
+        }
+    }
+    for acquire in &def.acquires_global_resources {
+        let def = table_entry(&module.struct_defs, acquire.0, "struct definition")?;
+        check_struct_handle(module, def.struct_handle)?;
+    }
+    Ok(())
+}
+
+fn check_struct_handle(module: &CompiledModuleMut, index: StructHandleIndex) -> Result<()> {
+    let handle = table_entry(&module.struct_handles, index.0, "struct handle")?;
+    table_entry(&module.identifiers, handle.name.0, "identifier")?;
+    let module_handle = table_entry(&module.module_handles, handle.module.0, "module handle")?;
+    table_entry(&module.identifiers, module_handle.name.0, "identifier")?;
+    table_entry(
+        &module.address_identifiers,
+        module_handle.address.0,
+        "address",
+    )?;
+    Ok(())
+}
+
+fn check_type_signature(module: &CompiledModuleMut, signature: &SignatureToken) -> Result<()> {
+    match signature {
+        SignatureToken::Vector(sign)
+        | SignatureToken::Reference(sign)
+        | SignatureToken::MutableReference(sign) => check_type_signature(module, sign),
+        SignatureToken::Struct(struct_index) => check_struct_handle(module, *struct_index),
+        SignatureToken::StructInstantiation(struct_index, typed) => {
+            check_struct_handle(module, *struct_index)?;
+            typed
+                .iter()
+                .try_for_each(|t| check_type_signature(module, t))
+        }
+        SignatureToken::U8
+        | SignatureToken::Bool
+        | SignatureToken::U64
+        | SignatureToken::U128
+        | SignatureToken::Address
+        | SignatureToken::Signer
+        | SignatureToken::TypeParameter(_) => Ok(()),
+    }
+}
+
+fn handle_index(index: u16, config: &Config) -> Option<u16> {
+    if config.handle_indices {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+fn visibility(is_public: bool, config: &Config) -> Option<&'static str> {
+    if !config.visibility_comments {
+        None
+    } else if is_public {
+        Some("public")
+    } else {
+        Some("private")
+    }
+}
+
+fn extract_type_params(params: &[Kind], config: &Config) -> TypeParams {
+    TypeParams {
+        params: params
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| TypeParam {
+                name: format!("{}{}", config.generic_prefix, i + 1),
+                kind: kind.to_owned(),
+            })
+            .collect(),
+    }
+}
+
+fn extract_fields(
+    module: &CompiledModuleMut,
+    info: &StructFieldInformation,
+    config: &Config,
+    imports: &mut Imports,
+) -> Vec<Field> {
+    if let StructFieldInformation::Declared(fields) = info {
+        fields
+            .iter()
+            .map(|def| Field {
+                name: module.identifiers[def.name.0 as usize].as_str().to_owned(),
+                f_type: extract_type_signature(module, &def.signature.0, config, imports),
+            })
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+fn extract_params(
+    module: &CompiledModuleMut,
+    info: &Signature,
+    config: &Config,
+    imports: &mut Imports,
+) -> Vec<Field> {
+    info.0
+        .iter()
+        .map(|param| extract_type_signature(module, param, config, imports))
+        .enumerate()
+        .map(|(i, param)| Field {
+            name: format!("{}{}", ARG_PREFIX, i + 1),
+            f_type: param,
+        })
+        .collect()
+}
+
+fn extract_return_value(
+    module: &CompiledModuleMut,
+    info: &Signature,
+    config: &Config,
+    imports: &mut Imports,
+) -> FuncResult {
+    FuncResult {
+        ret: info
+            .0
+            .iter()
+            .map(|param| extract_type_signature(module, param, config, imports))
+            .collect(),
+    }
+}
+
+fn extract_type_signature(
+    module: &CompiledModuleMut,
+    signature: &SignatureToken,
+    config: &Config,
+    imports: &mut Imports,
+) -> FType {
+    match signature {
+        SignatureToken::U8 => FType::U8,
+        SignatureToken::Bool => FType::Bool,
+        SignatureToken::U64 => FType::U64,
+        SignatureToken::U128 => FType::U128,
+        SignatureToken::Address => FType::Address,
+        SignatureToken::Signer => FType::Signer,
+        SignatureToken::Vector(sign) => FType::Vec(Box::new(extract_type_signature(
+            module,
+            sign.as_ref(),
+            config,
+            imports,
+        ))),
+        SignatureToken::Struct(struct_index) => FType::Struct(extract_strict_full_name(
+            module,
+            *struct_index,
+            config,
+            imports,
+        )),
+        SignatureToken::StructInstantiation(struct_index, typed) => FType::StructInst(
+            extract_strict_full_name(module, *struct_index, config, imports),
+            typed
+                .iter()
+                .map(|t| extract_type_signature(module, t, config, imports))
+                .collect(),
+        ),
+        SignatureToken::Reference(sign) => FType::Ref(Box::new(extract_type_signature(
+            module,
+            sign.as_ref(),
+            config,
+            imports,
+        ))),
+        SignatureToken::MutableReference(sign) => FType::RefMut(Box::new(extract_type_signature(
+            module,
+            sign.as_ref(),
+            config,
+            imports,
+        ))),
+        SignatureToken::TypeParameter(index) => {
+            FType::Generic(format!("{}{}", config.generic_prefix, index + 1))
+        }
+    }
+}
+
+fn extract_strict_full_name(
+    module: &CompiledModuleMut,
+    struct_index: StructHandleIndex,
+    config: &Config,
+    imports: &mut Imports,
+) -> FullStructName {
+    let handler = &module.struct_handles[struct_index.0 as usize];
+    let mut name = module.identifiers[handler.name.0 as usize]
+        .as_str()
+        .to_owned();
+    let module_handler = &module.module_handles[handler.module.0 as usize];
+    let module_name = module.identifiers[module_handler.name.0 as usize].as_str();
+    let address = &module.address_identifiers[module_handler.address.0 as usize];
+
+    let import = if is_self_module(module, handler.module) {
+        name = config.rename(&name);
+        None
+    } else {
+        Some(imports.add(address, module_name))
+    };
+
+    FullStructName {
+        address: *address,
+        module: module_name.to_owned(),
+        name,
+        import,
+    }
+}
+
+/// Returns true if the module handle refers to the module itself.
+/// Handles are compared by address and name since the self module is not necessarily referenced by the first handle only.
+fn is_self_module(module: &CompiledModuleMut, index: ModuleHandleIndex) -> bool {
+    let self_handle = &module.module_handles[0];
+    let handle = &module.module_handles[index.0 as usize];
+    module.address_identifiers[handle.address.0 as usize]
+        == module.address_identifiers[self_handle.address.0 as usize]
+        && module.identifiers[handle.name.0 as usize]
+            == module.identifiers[self_handle.name.0 as usize]
+}
+
+fn extract_functions(
+    module: &CompiledModuleMut,
+    config: &Config,
+    imports: &mut Imports,
+) -> Functions {
+    let mut names = BTreeSet::new();
+    let reserved = config
+        .source_names
+        .as_ref()
+        .map(|_| reserved_names(module, config));
+    let functions = module
+        .function_defs
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| {
+            config.function_filter.map_or(true, |filter| {
+                let handler = &module.function_handles[def.function.0 as usize];
+                filter(module.identifiers[handler.name.0 as usize].as_str())
+            })
+        })
+        .map(|(index, def)| {
+            let handler = &module.function_handles[def.function.0 as usize];
+            let name = module.identifiers[handler.name.0 as usize].to_string();
+            let duplicate = !names.insert(name.clone());
+            let signatures = &module.signatures[handler.parameters.0 as usize];
+            let spec = config.specs.and_then(|specs| specs.get(&name).cloned());
+            let attributes = config
+                .attributes
+                .and_then(|attributes| attributes.get(&name).cloned())
+                .unwrap_or_default();
+
+            let (instructions, acquires) = if !def.is_native() {
+                let mut body = Vec::new();
+                let mut acquires = Vec::new();
+
+                if !config.only_interface {
+                    for acquire in &def.acquires_global_resources {
+                        let struct_defs = &module.struct_defs[acquire.0 as usize];
+                        let handler = &module.struct_handles[struct_defs.struct_handle.0 as usize];
+                        let name =
+                            config.rename(module.identifiers[handler.name.0 as usize].as_str());
+
+                        if handler.type_parameters.is_empty() {
+                            body.push(Instruction::Borrow(name.to_string()));
+                        } else {
+                            let params = handler
+                                .type_parameters
+                                .iter()
+                                .map(|param| match param {
+                                    Kind::Resource => config.phantom_resource_name.to_string(),
+                                    Kind::All | Kind::Copyable => "u64".to_string(),
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            body.push(Instruction::Borrow(format!("{}<{}>", name, params)));
+                        }
+
+                        acquires.push(name);
+                    }
+
+                    body.push(Instruction::Abort(1));
+                }
+                (body, acquires)
+            } else {
+                (vec![], vec![])
+            };
+            let mut function = Function {
+                is_public: def.is_public(),
+                is_native: def.is_native(),
+                name: config.rename(&name),
+                type_params: extract_type_params(&handler.type_parameters, config),
+                params: Params {
+                    fields: extract_params(module, &signatures, config, imports),
+                    indent_size: 0,
+                    is_struct_field: false,
+                    trailing_comma: false,
+                    align: false,
+                },
+                ret: extract_return_value(
+                    module,
+                    &module.signatures[handler.return_.0 as usize],
+                    config,
+                    imports,
+                ),
+                acquires: Acquires { inner: acquires },
+                spec,
+                attributes,
+                handle_index: handle_index(def.function.0, config),
+                visibility: visibility(def.is_public(), config),
+                duplicate,
+                indent_size: config.indent,
+                body: Block {
+                    instructions,
+                    indent_size: config.indent,
+                    instructions_indent_size: config.indent * 2,
+                },
+            };
+            let source_names = config
+                .source_names
+                .as_ref()
+                .and_then(|source_names| source_names.functions.get(&index));
+            if let (Some(source_names), Some(reserved)) = (source_names, &reserved) {
+                source_names.apply_to_function(&mut function, config.generic_prefix, reserved);
+            }
+            if config.bytecode_listing && !config.only_interface {
+                let locals = function
+                    .params
+                    .fields
+                    .iter()
+                    .map(|param| param.name.to_owned())
+                    .collect::<Vec<_>>();
+                let listing = extract_listing(module, def, &locals, config, imports)
+                    .into_iter()
+                    .map(Instruction::Comment);
+                function.body.instructions.splice(0..0, listing);
+            }
+            function
+        })
+        .collect();
+    Functions { functions }
+}
+
+/// Renders the function instructions as `L<offset>: <mnemonic> <operand>` lines.
+/// Locals, functions, structs and fields are referred to by name, branches by label.
+/// Type arguments are named after the module imports.
+fn extract_listing(
+    module: &CompiledModuleMut,
+    def: &FunctionDefinition,
+    params: &[String],
+    config: &Config,
+    imports: &mut Imports,
+) -> Vec<String> {
+    let code = match &def.code {
+        Some(code) => code,
+        None => return vec![],
+    };
+    let local = |index: u8| {
+        params
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("_loc_{}", index as usize + 1 - params.len()))
+    };
+    let mut type_args = |index: SignatureIndex| {
+        let args = module.signatures[index.0 as usize]
+            .0
+            .iter()
+            .map(|arg| extract_type_signature(module, arg, config, imports).to_string())
+            .collect::<Vec<_>>();
+        format!("<{}>", args.join(", "))
+    };
+    let function = |handle: FunctionHandleIndex| {
+        let handler = &module.function_handles[handle.0 as usize];
+        let name = module.identifiers[handler.name.0 as usize].as_str();
+        if is_self_module(module, handler.module) {
+            config.rename(name)
+        } else {
+            let module_handler = &module.module_handles[handler.module.0 as usize];
+            format!(
+                "0x{}::{}::{}",
+                module.address_identifiers[module_handler.address.0 as usize],
+                module.identifiers[module_handler.name.0 as usize],
+                name
+            )
+        }
+    };
+    let struct_name = |index: StructDefinitionIndex| {
+        let def = &module.struct_defs[index.0 as usize];
+        let handler = &module.struct_handles[def.struct_handle.0 as usize];
+        config.rename(module.identifiers[handler.name.0 as usize].as_str())
+    };
+    let field = |index: FieldHandleIndex| {
+        let handle = &module.field_handles[index.0 as usize];
+        let def = &module.struct_defs[handle.owner.0 as usize];
+        let name = match &def.field_information {
+            StructFieldInformation::Declared(fields) => fields
+                .get(handle.field as usize)
+                .map(|field| module.identifiers[field.name.0 as usize].to_string()),
+            StructFieldInformation::Native => None,
+        };
+        format!(
+            "{}.{}",
+            struct_name(handle.owner),
+            name.unwrap_or_else(|| handle.field.to_string())
+        )
+    };
+
+    code.code
+        .iter()
+        .enumerate()
+        .map(|(offset, instruction)| {
+            let operand = match instruction {
+                Bytecode::CopyLoc(index)
+                | Bytecode::MoveLoc(index)
+                | Bytecode::StLoc(index)
+                | Bytecode::MutBorrowLoc(index)
+                | Bytecode::ImmBorrowLoc(index) => Some(local(*index)),
+                Bytecode::Call(handle) => Some(function(*handle)),
+                Bytecode::CallGeneric(index) => {
+                    let inst = &module.function_instantiations[index.0 as usize];
+                    Some(function(inst.handle) + &type_args(inst.type_parameters))
+                }
+                Bytecode::Pack(index)
+                | Bytecode::Unpack(index)
+                | Bytecode::MutBorrowGlobal(index)
+                | Bytecode::ImmBorrowGlobal(index)
+                | Bytecode::Exists(index)
+                | Bytecode::MoveFrom(index)
+                | Bytecode::MoveTo(index)
+                | Bytecode::MoveToSender(index) => Some(struct_name(*index)),
+                Bytecode::PackGeneric(index)
+                | Bytecode::UnpackGeneric(index)
+                | Bytecode::MutBorrowGlobalGeneric(index)
+                | Bytecode::ImmBorrowGlobalGeneric(index)
+                | Bytecode::ExistsGeneric(index)
+                | Bytecode::MoveFromGeneric(index)
+                | Bytecode::MoveToGeneric(index)
+                | Bytecode::MoveToSenderGeneric(index) => {
+                    let inst = &module.struct_def_instantiations[index.0 as usize];
+                    Some(struct_name(inst.def) + &type_args(inst.type_parameters))
+                }
+                Bytecode::MutBorrowField(index) | Bytecode::ImmBorrowField(index) => {
+                    Some(field(*index))
+                }
+                Bytecode::MutBorrowFieldGeneric(index) | Bytecode::ImmBorrowFieldGeneric(index) => {
+                    Some(field(module.field_instantiations[index.0 as usize].handle))
+                }
+                Bytecode::BrTrue(offset) | Bytecode::BrFalse(offset) | Bytecode::Branch(offset) => {
+                    Some(format!("L{}", offset))
+                }
+                Bytecode::LdU8(value) => Some(value.to_string()),
+                Bytecode::LdU64(value) => Some(value.to_string()),
+                Bytecode::LdU128(value) => Some(value.to_string()),
+                Bytecode::LdConst(index) => Some(format!("#{}", index.0)),
+                _ => None,
+            };
+            let mnemonic = format!("{:?}", instruction);
+            let mnemonic = mnemonic.split('(').next().unwrap_or_default();
+            match operand {
+                Some(operand) => format!("L{}: {} {}", offset, mnemonic, operand),
+                None => format!("L{}: {}", offset, mnemonic),
+            }
+        })
+        .collect()
+}
+
+/// Original names taken from a source map keyed by definition index.
+struct SourceNames {
+    structs: BTreeMap<usize, DefinitionNames>,
+    functions: BTreeMap<usize, DefinitionNames>,
+}
+
+/// Original names of the type parameters and locals of a definition.
+/// Locals start with the function parameters. Structs have no locals.
+struct DefinitionNames {
+    type_params: Vec<String>,
+    locals: Vec<String>,
+}
+
+impl DefinitionNames {
+    /// Replaces the synthesized names of the function type parameters and parameters.
+    fn apply_to_function(
+        &self,
+        function: &mut Function,
+        generic_prefix: &str,
+        reserved: &BTreeSet<String>,
+    ) {
+        let (type_params, locals) = self.usable(generic_prefix, reserved);
+        for (param, name) in function.type_params.params.iter_mut().zip(&type_params) {
+            if let Some(name) = name {
+                param.name = name.to_owned();
+            }
+        }
+        for (param, name) in function.params.fields.iter_mut().zip(&locals) {
+            if let Some(name) = name {
+                param.name = name.to_owned();
+            }
+            rename_generics(&mut param.f_type, generic_prefix, &type_params);
+        }
+        for f_type in &mut function.ret.ret {
+            rename_generics(f_type, generic_prefix, &type_params);
+        }
+    }
+
+    /// Replaces the synthesized names of the struct type parameters.
+    fn apply_to_struct(&self, def: &mut Struct, generic_prefix: &str, reserved: &BTreeSet<String>) {
+        let (type_params, _) = self.usable(generic_prefix, reserved);
+        for (param, name) in def.type_params.params.iter_mut().zip(&type_params) {
+            if let Some(name) = name {
+                param.name = name.to_owned();
+            }
+        }
+        for field in &mut def.fields.fields {
+            rename_generics(&mut field.f_type, generic_prefix, &type_params);
+        }
+    }
+
+    /// Returns the original type parameter and local names which can replace the synthesized ones.
+    /// A name is skipped if it is not a valid identifier, is reserved, looks like a synthesized name
+    /// or is already taken by another name of the definition.
+    fn usable(
+        &self,
+        generic_prefix: &str,
+        reserved: &BTreeSet<String>,
+    ) -> (Vec<Option<String>>, Vec<Option<String>>) {
+        let mut taken = BTreeSet::new();
+        let mut usable = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| {
+                    let synthesized = [generic_prefix, ARG_PREFIX].iter().any(|prefix| {
+                        name.starts_with(prefix)
+                            && name[prefix.len()..].chars().all(|c| c.is_ascii_digit())
+                    });
+                    if Identifier::is_valid(name)
+                        && !synthesized
+                        && !reserved.contains(name)
+                        && taken.insert(name.to_owned())
+                    {
+                        Some(name.to_owned())
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let type_params = usable(&self.type_params);
+        let locals = usable(&self.locals);
+        (type_params, locals)
+    }
+}
+
+/// Returns the names the original names must not take: the module identifiers,
+/// the struct renames and the phantom resource name.
+fn reserved_names(module: &CompiledModuleMut, config: &Config) -> BTreeSet<String> {
+    let mut reserved = IdentifierAllocator::new(module).used;
+    if let Some(renames) = config.renames {
+        reserved.extend(renames.values().cloned());
+    }
+    reserved.insert(config.phantom_resource_name.to_owned());
+    reserved
+}
+
+/// Replaces the synthesized generic names with the original type parameter names.
+fn rename_generics(f_type: &mut FType, generic_prefix: &str, type_params: &[Option<String>]) {
+    match f_type {
+        FType::Generic(name) if name.starts_with(generic_prefix) => {
+            let original = name[generic_prefix.len()..]
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| index.checked_sub(1))
+                .and_then(|index| type_params.get(index))
+                .and_then(Option::as_ref);
+            if let Some(original) = original {
+                *name = original.to_owned();
+            }
+        }
+        FType::Vec(f_type) | FType::Ref(f_type) | FType::RefMut(f_type) => {
+            rename_generics(f_type, generic_prefix, type_params)
+        }
+        FType::StructInst(_, params) => {
+            for param in params {
+                rename_generics(param, generic_prefix, type_params);
+            }
+        }
+        FType::Generic(_)
+        | FType::U8
+        | FType::U64
+        | FType::U128
+        | FType::Bool
+        | FType::Address
+        | FType::Signer
+        | FType::Struct(_) => {}
+    }
+}
+
+enum Instruction {
+    Abort(u8),
+    Borrow(String),
+    Comment(String),
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Abort(code) => write!(f, "abort {}", code),
+            Instruction::Borrow(resources) => write!(f, "borrow_global<{}>(0x0);", resources),
+            Instruction::Comment(comment) => write!(f, "// {}", comment),
+        }
+    }
+}
+
+/// Allocates identifiers which don't collide with the names used in the module.
+/// Used for names synthesized by the disassembler.
+pub struct IdentifierAllocator {
+    used: BTreeSet<String>,
+}
+
+impl IdentifierAllocator {
+    /// Create an allocator aware of all identifiers of the module.
+    pub fn new(module: &CompiledModuleMut) -> IdentifierAllocator {
+        IdentifierAllocator {
+            used: module
+                .identifiers
+                .iter()
+                .map(|ident| ident.as_str().to_owned())
+                .collect(),
+        }
+    }
+
+    /// Returns a fresh identifier starting with the prefix and marks it as used.
+    pub fn allocate(&mut self, prefix: &str) -> String {
+        let mut index = 1;
+        loop {
+            let name = format!("{}{}", prefix, index);
+            if self.used.insert(name.clone()) {
+                return name;
+            }
+            index += 1;
+        }
+    }
+
+    /// Returns a prefix such that no used identifier consists of the prefix followed by a number.
+    /// The prefix is returned as is if it doesn't collide.
+    pub fn fresh_prefix(&self, prefix: &str) -> String {
+        let mut candidate = prefix.to_owned();
+        let mut index = 1;
+        while self.used.iter().any(|name| {
+            name.starts_with(&candidate)
+                && name.len() > candidate.len()
+                && name[candidate.len()..].chars().all(|c| c.is_ascii_digit())
+        }) {
+            candidate = format!("{}{}_", prefix, index);
+            index += 1;
+        }
+        candidate
+    }
+}
+
+struct Imports {
+    uses: BTreeMap<String, BTreeMap<AccountAddress, Option<String>>>,
+    indent_size: usize,
+}
+
+impl Imports {
+    pub fn new() -> Imports {
+        Imports {
+            uses: Default::default(),
+            indent_size: 4,
+        }
+    }
+
+    pub fn add(&mut self, address: &AccountAddress, name: &str) -> String {
+        if let Some(alias) = self.uses.get(name).and_then(|aliases| aliases.get(address)) {
+            return alias.as_deref().unwrap_or(name).to_owned();
+        }
+
+        let index = self
+            .uses
+            .get(name)
+            .map(|aliases| aliases.len())
+            .unwrap_or(0);
+        let alias = if index == 0 && !self.is_alias(name) {
+            None
+        } else {
+            Some(self.fresh_alias(name, index))
+        };
+        self.uses
+            .entry(name.to_owned())
+            .or_default()
+            .insert(*address, alias.clone());
+        alias.unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Returns true if the name is used as an alias of another module.
+    fn is_alias(&self, name: &str) -> bool {
+        self.uses
+            .values()
+            .flat_map(|aliases| aliases.values())
+            .any(|alias| alias.as_deref() == Some(name))
+    }
+
+    /// Returns an alias which is used neither as a module name nor as another alias.
+    fn fresh_alias(&self, name: &str, mut index: usize) -> String {
+        loop {
+            let alias = format!("Other{}{}", name, index);
+            if !self.uses.contains_key(&alias) && !self.is_alias(&alias) {
+                return alias;
+            }
+            index += 1;
+        }
+    }
+}
+
+impl Display for Imports {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (ident, aliases) in &self.uses {
+            for (addr, alias) in aliases {
+                if let Some(alias) = alias {
+                    writeln!(
+                        f,
+                        "{:width$}use 0x{address}::{name} as {alias};",
+                        "",
+                        address = addr,
+                        name = ident,
+                        width = self.indent_size,
+                        alias = alias
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        "{:width$}use 0x{address}::{name};",
+                        "",
+                        address = addr,
+                        name = ident,
+                        width = self.indent_size,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct TypeParam {
+    name: String,
+    kind: Kind,
+}
+
+impl Display for TypeParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::All => write!(f, "{}", self.name),
+            Kind::Resource => write!(f, "{}: resource", self.name),
+            Kind::Copyable => write!(f, "{}: copyable", self.name),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TypeParams {
+    params: Vec<TypeParam>,
+}
+
+impl Display for TypeParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.params.is_empty() {
+            write!(
+                f,
+                "<{}>",
+                self.params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Type of a field, parameter or return value.
+#[derive(Hash)]
+pub enum FType {
+    Generic(String),
+    U8,
+    U64,
+    U128,
+    Bool,
+    Address,
+    Signer,
+    Vec(Box<FType>),
+    Struct(FullStructName),
+    StructInst(FullStructName, Vec<FType>),
+    Ref(Box<FType>),
+    RefMut(Box<FType>),
+}
+
+impl FType {
+    /// Converts the type into the libra `TypeTag`.
+    /// Returns `None` for generics and references which have no `TypeTag` equivalent.
+    pub fn to_type_tag(&self) -> Option<TypeTag> {
+        Some(match self {
+            FType::U8 => TypeTag::U8,
+            FType::U64 => TypeTag::U64,
+            FType::U128 => TypeTag::U128,
+            FType::Bool => TypeTag::Bool,
+            FType::Address => TypeTag::Address,
+            FType::Signer => TypeTag::Signer,
+            FType::Vec(f_type) => TypeTag::Vector(Box::new(f_type.to_type_tag()?)),
+            FType::Struct(name) => TypeTag::Struct(name.to_struct_tag(vec![])?),
+            FType::StructInst(name, params) => TypeTag::Struct(
+                name.to_struct_tag(
+                    params
+                        .iter()
+                        .map(FType::to_type_tag)
+                        .collect::<Option<Vec<_>>>()?,
+                )?,
+            ),
+            FType::Generic(_) | FType::Ref(_) | FType::RefMut(_) => return None,
+        })
+    }
+}
+
+impl Display for FType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FType::Generic(name) => write!(f, "{}", name),
+            FType::U8 => write!(f, "u8"),
+            FType::U64 => write!(f, "u64"),
+            FType::U128 => write!(f, "u128"),
+            FType::Bool => write!(f, "bool"),
+            FType::Address => write!(f, "address"),
+            FType::Signer => write!(f, "signer"),
+            FType::Vec(f_type) => write!(f, "vector<{}>", f_type),
+            FType::Struct(name) => write!(f, "{}", name),
+            FType::StructInst(name, params) => write!(
+                f,
+                "{}<{}>",
+                name,
+                params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            FType::Ref(f_type) => write!(f, "&{}", f_type),
+            FType::RefMut(f_type) => write!(f, "&mut {}", f_type),
+        }
+    }
+}
+
+/// Struct name with the module it is declared in.
+pub struct FullStructName {
+    address: AccountAddress,
+    module: String,
+    name: String,
+    /// Import alias of the declaring module. `None` for the structs of the module itself.
+    import: Option<String>,
+}
+
+impl FullStructName {
+    fn to_struct_tag(&self, type_params: Vec<TypeTag>) -> Option<StructTag> {
+        Some(StructTag {
+            address: self.address,
+            module: Identifier::new(self.module.to_owned()).ok()?,
+            name: Identifier::new(self.name.to_owned()).ok()?,
+            type_params,
+        })
+    }
+}
+
+// The import alias depends on the rest of the module, so it doesn't take part in the hash.
+impl Hash for FullStructName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+        self.module.hash(state);
+        self.name.hash(state);
+    }
+}
+
+impl Display for FullStructName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(import) = &self.import {
+            write!(f, "{}::{}", import, self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+/// Struct field or function parameter.
+pub struct Field {
+    name: String,
+    f_type: FType,
+}
+
+impl Field {
+    /// Returns the field name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the field type.
+    pub fn f_type(&self) -> &FType {
+        &self.f_type
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.f_type)
+    }
+}
+
+#[derive(Default)]
+struct Params {
+    fields: Vec<Field>,
+    indent_size: usize,
+    is_struct_field: bool,
+    trailing_comma: bool,
+    /// Pad the field names to the longest one.
+    align: bool,
+}
+
+impl Display for Params {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name_width = if self.align {
+            self.fields
+                .iter()
+                .map(|field| field.name.len())
+                .max()
+                .unwrap_or_default()
+        } else {
+            0
+        };
+
+        for (i, field) in self.fields.iter().enumerate() {
+            write!(
+                f,
+                "{s:width$}{name:name_width$}: {f_type}{end}",
+                name = field.name,
+                name_width = name_width,
+                f_type = field.f_type,
+                s = "",
+                width = self.indent_size,
+                end = match (self.is_struct_field, i == self.fields.len() - 1) {
+                    (true, true) if !self.trailing_comma => "\n",
+                    (true, _) => ",\n",
+                    (false, true) => "",
+                    (false, false) => ", ",
+                }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Struct definition.
+pub struct Struct {
+    is_nominal_resource: bool,
+    is_native: bool,
+    name: String,
+    type_params: TypeParams,
+    handle_index: Option<u16>,
+    visibility: Option<&'static str>,
+    /// The struct name is already taken by another struct of the module.
+    duplicate: bool,
+    indent_size: usize,
+    fields: Params,
+}
+
+impl Struct {
+    /// Returns true if the struct is a resource.
+    pub fn is_resource(&self) -> bool {
+        self.is_nominal_resource
+    }
+
+    /// Returns true if the struct is native.
+    pub fn is_native(&self) -> bool {
+        self.is_native
+    }
+
+    /// Returns the struct name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the struct fields.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields.fields
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let nominal_name = if self.is_nominal_resource {
+            "resource struct"
+        } else if self.is_native {
+            "native struct"
+        } else {
+            "struct"
+        };
+
+        if let Some(index) = self.handle_index {
+            writeln!(
+                f,
+                "{s:width$}// struct_handle #{index}",
+                s = "",
+                width = self.indent_size,
+                index = index
+            )?;
+        }
+        if let Some(visibility) = self.visibility {
+            writeln!(
+                f,
+                "{s:width$}// {visibility}",
+                s = "",
+                width = self.indent_size,
+                visibility = visibility
+            )?;
+        }
+        if self.duplicate {
+            writeln!(
+                f,
+                "{s:width$}// duplicate struct name '{name}'",
+                s = "",
+                width = self.indent_size,
+                name = self.name
+            )?;
+        }
+
+        if self.is_native {
+            writeln!(
+                f,
+                "{s:width$}{nominal_name} {name}{params};",
+                s = "",
+                width = self.indent_size,
+                nominal_name = nominal_name,
+                name = self.name,
+                params = self.type_params,
+            )
+        } else {
+            writeln!(
+                f,
+                "{s:width$}{nominal_name} {name}{params} {{\n{fields}{s:width$}}}",
+                s = "",
+                width = self.indent_size,
+                nominal_name = nominal_name,
+                name = self.name,
+                params = self.type_params,
+                fields = self.fields,
+            )
+        }
+    }
+}
+
+struct Structs {
+    structs: Vec<Struct>,
+    /// Errors of the structs which can't be decoded.
+    errors: Vec<String>,
+    indent_size: usize,
+}
+
+impl Structs {
+    pub fn contains(&self, name: &str) -> bool {
+        self.structs.iter().any(|s| s.name == name)
+    }
+}
+
+impl Display for Structs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for err in &self.errors {
+            writeln!(
+                f,
+                "{s:width$}// <{err}>\n",
+                s = "",
+                width = self.indent_size,
+                err = err
+            )?
+        }
+        for s in &self.structs {
+            writeln!(f, "{}", s)?
+        }
+        Ok(())
+    }
+}
+
+struct FuncResult {
+    ret: Vec<FType>,
+}
+
+impl Display for FuncResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.ret.len() {
+            0 => Ok(()),
+            1 => write!(f, ": {}", self.ret[0]),
+            _ => write!(
+                f,
+                ": ({})",
+                self.ret
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+struct Acquires {
+    inner: Vec<String>,
+}
+
+impl Display for Acquires {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.inner.is_empty() {
+            write!(f, " acquires {}", self.inner.join(", "))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct Block {
+    instructions: Vec<Instruction>,
+    indent_size: usize,
+    instructions_indent_size: usize,
+}
+
+impl Display for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{{")?;
+        for i in &self.instructions {
             writeln!(
                 f,
-                "{s:width$}{nominal_name} {name}{params} {{\n{fields}{s:width$}}}",
+                "{s:width$}{i}",
+                s = "",
+                width = self.instructions_indent_size,
+                i = i
+            )?;
+        }
+        writeln!(f, "{s:width$}}}", s = "", width = self.indent_size)?;
+        Ok(())
+    }
+}
+
+struct Function {
+    is_public: bool,
+    is_native: bool,
+    name: String,
+    type_params: TypeParams,
+    params: Params,
+    ret: FuncResult,
+    acquires: Acquires,
+    spec: Option<String>,
+    attributes: Vec<String>,
+    handle_index: Option<u16>,
+    visibility: Option<&'static str>,
+    /// The function name is already taken by another function of the module.
+    duplicate: bool,
+    indent_size: usize,
+    body: Block,
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(spec) = &self.spec {
+            for line in spec.lines() {
+                writeln!(
+                    f,
+                    "{s:width$}// {line}",
+                    s = "",
+                    width = self.indent_size,
+                    line = line.trim_end()
+                )?;
+            }
+        }
+        if let Some(index) = self.handle_index {
+            writeln!(
+                f,
+                "{s:width$}// function_handle #{index}",
                 s = "",
                 width = self.indent_size,
-                nominal_name = nominal_name,
-                name = self.name,
-                params = self.type_params,
-                fields = self.fields,
+                index = index
+            )?;
+        }
+        if let Some(visibility) = self.visibility {
+            writeln!(
+                f,
+                "{s:width$}// {visibility}",
+                s = "",
+                width = self.indent_size,
+                visibility = visibility
+            )?;
+        }
+        if self.duplicate {
+            writeln!(
+                f,
+                "{s:width$}// duplicate function name '{name}'",
+                s = "",
+                width = self.indent_size,
+                name = self.name
+            )?;
+        }
+        for attribute in &self.attributes {
+            writeln!(
+                f,
+                "{s:width$}#[{attribute}]",
+                s = "",
+                width = self.indent_size,
+                attribute = attribute
+            )?;
+        }
+        write!(
+            f,
+            "{s:width$}{native}{p}fun {name}{t_params}({params}){return_}{acquires}{native_end}",
+            s = "",
+            width = self.indent_size,
+            p = if self.is_public { "public " } else { "" },
+            native = if self.is_native { "native " } else { "" },
+            name = self.name,
+            t_params = self.type_params,
+            params = self.params,
+            return_ = self.ret,
+            acquires = self.acquires,
+            native_end = if self.is_native { ";\n" } else { "" },
+        )?;
+        if !self.is_native {
+            write!(f, " {}", self.body)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Functions {
+    functions: Vec<Function>,
+}
+
+impl Functions {
+    pub fn has_acursors(&self) -> bool {
+        self.functions.iter().any(|f| !f.acquires.inner.is_empty())
+    }
+}
+
+impl Display for Functions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for s in &self.functions {
+            writeln!(f, "{}", s)?
+        }
+        Ok(())
+    }
+}
+
+pub struct ModuleSignature {
+    id: ModuleId,
+    structs: Structs,
+    functions: Functions,
+    imports: Imports,
+    compact: bool,
+    header: Option<String>,
+}
+
+impl ModuleSignature {
+    pub fn self_id(&self) -> &ModuleId {
+        &self.id
+    }
+
+    /// Returns the module structs.
+    pub fn structs(&self) -> &[Struct] {
+        &self.structs.structs
+    }
+
+    /// Returns the module metadata: structs with their fields and functions with their signatures.
+    /// Types are rendered with the names of the signature configuration.
+    pub fn abi(&self) -> ModuleAbi {
+        let kinds = |params: &TypeParams| {
+            params
+                .params
+                .iter()
+                .map(|param| kind_name(param.kind))
+                .collect::<Vec<_>>()
+        };
+        ModuleAbi {
+            address: format!("0x{}", self.id.address()),
+            name: self.id.name().to_string(),
+            structs: self
+                .structs
+                .structs
+                .iter()
+                .map(|s| StructAbi {
+                    name: s.name.to_owned(),
+                    resource: s.is_nominal_resource,
+                    native: s.is_native,
+                    type_params: kinds(&s.type_params),
+                    fields: s
+                        .fields
+                        .fields
+                        .iter()
+                        .map(|field| FieldAbi {
+                            name: field.name.to_owned(),
+                            f_type: abi_type(&field.f_type),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            functions: self
+                .functions
+                .functions
+                .iter()
+                .map(|f| FunctionAbi {
+                    name: f.name.to_owned(),
+                    public: f.is_public,
+                    native: f.is_native,
+                    type_params: kinds(&f.type_params),
+                    params: f
+                        .params
+                        .fields
+                        .iter()
+                        .map(|p| ParamAbi {
+                            f_type: abi_type(&p.f_type),
+                            is_signer: is_signer(&p.f_type),
+                        })
+                        .collect(),
+                    returns: f.ret.ret.iter().map(abi_type).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for ModuleSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.compact {
+            let mut source = String::new();
+            self.write_pretty(&mut source)?;
+            writeln!(f, "{}", compact(&source))
+        } else {
+            self.write_pretty(f)
+        }
+    }
+}
+
+impl ModuleSignature {
+    /// Streams the module source into the writer without building the whole string.
+    /// Compact modules are buffered since they are joined into a single line.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        use fmt::Write;
+
+        let mut adapter = IoAdapter {
+            inner: w,
+            error: None,
+        };
+        let result = if self.compact {
+            write!(adapter, "{}", self)
+        } else {
+            self.write_pretty(&mut adapter)
+        };
+        match (result, adapter.error) {
+            (_, Some(err)) => Err(err),
+            (Err(_), None) => Err(io::Error::new(io::ErrorKind::Other, "formatter error")),
+            (Ok(()), None) => Ok(()),
+        }
+    }
+
+    /// Writes the module with indentation and line breaks.
+    fn write_pretty<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        if let Some(header) = &self.header {
+            for line in header.lines() {
+                writeln!(w, "// {}", line.trim_end())?;
+            }
+        }
+        writeln!(
+            w,
+            "address 0x{address} {{\n\nmodule {name} {{\n{imports}{structs}{functions}}}\n}}",
+            address = self.id.address(),
+            name = self.id.name(),
+            structs = self.structs,
+            functions = self.functions,
+            imports = self.imports,
+        )
+    }
+}
+
+pub struct ScriptSignature {
+    functions: Functions,
+    imports: Imports,
+}
+
+impl Display for ScriptSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "script {{
+{imports}{functions}}}",
+            functions = self.functions,
+            imports = self.imports,
+        )
+    }
+}
+
+/// Forwards `fmt::Write` output to an `io::Write` and keeps the first io error.
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> fmt::Write for IoAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Kind of the outline node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineKind {
+    Module,
+    Struct,
+    Field,
+    Function,
+    Param,
+}
+
+/// Node of the module outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineNode {
+    name: String,
+    kind: OutlineKind,
+    children: Vec<OutlineNode>,
+}
+
+impl OutlineNode {
+    fn new(name: &str, kind: OutlineKind, children: Vec<OutlineNode>) -> OutlineNode {
+        OutlineNode {
+            name: name.to_owned(),
+            kind,
+            children,
+        }
+    }
+
+    /// Returns the node name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the node kind.
+    pub fn kind(&self) -> OutlineKind {
+        self.kind
+    }
+
+    /// Returns the nested nodes.
+    pub fn children(&self) -> &[OutlineNode] {
+        &self.children
+    }
+}
+
+/// Kind of the disassembled token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    Identifier,
+    /// Primitive type name.
+    Type,
+    /// Number or address.
+    Literal,
+    Punctuation,
+    Comment,
+}
+
+/// Token of the disassembled module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    text: String,
+    kind: TokenKind,
+    offset: usize,
+}
+
+impl Token {
+    /// Returns the token text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the token kind.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// Returns the byte offset of the token.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "module", "struct", "resource", "native", "public", "fun", "use", "as", "acquires", "abort",
+    "mut", "copyable",
+];
+const PRIMITIVE_TYPES: &[&str] = &["u8", "u64", "u128", "bool", "address", "signer", "vector"];
+
+/// Splits the disassembled source into tokens.
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+
+    while let Some(ch) = source[offset..].chars().next() {
+        let rest = &source[offset..];
+        let (len, kind) = if ch.is_whitespace() {
+            offset += ch.len_utf8();
+            continue;
+        } else if rest.starts_with("//") {
+            (
+                rest.find('\n').unwrap_or_else(|| rest.len()),
+                TokenKind::Comment,
+            )
+        } else if ch.is_alphanumeric() || ch == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or_else(|| rest.len());
+            let word = &rest[..len];
+            let kind = if ch.is_ascii_digit() {
+                TokenKind::Literal
+            } else if word == "address"
+                && rest[len..]
+                    .trim_start()
+                    .starts_with(|c: char| c.is_ascii_digit())
+            {
+                // Address block header: `address 0x1 {`.
+                TokenKind::Keyword
+            } else if PRIMITIVE_TYPES.contains(&word) {
+                TokenKind::Type
+            } else if KEYWORDS.contains(&word) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Identifier
+            };
+            (len, kind)
+        } else {
+            (ch.len_utf8(), TokenKind::Punctuation)
+        };
+
+        tokens.push(Token {
+            text: rest[..len].to_owned(),
+            kind,
+            offset,
+        });
+        offset += len;
+    }
+    tokens
+}
+
+/// Collapses indentation and line breaks into single spaces.
+/// Line comments are converted to block comments so that they don't swallow the rest of the line,
+/// `/*` and `*/` inside a comment are split so that they don't open or close a block comment.
+/// The disassembled code never contains `//` outside of comments, so it always starts a comment.
+fn compact(source: &str) -> String {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.find("//") {
+            Some(start) => format!(
+                "{}/*{} */",
+                &line[..start],
+                line[start + 2..].replace("/*", "/ *").replace("*/", "* /")
+            ),
+            None => line.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::libra_types::account_address::AccountAddress;
+    use ds::MockDataSource;
+    use crate::embedded::{Compiler, TempDir};
+    use crate::embedded::ds_loader::StateViewLoader;
+    use crate::mv::dependence::extractor::{extract_bytecode_tree, extract_from_bytecode};
+    use crate::mv::dependence::loader::InMemorySource;
+    use crate::mv::disassembler::{
+        module_signature, module_signature_with_configuration, Config, script_arg_types,
+        script_type_params_count, reachable_module_signatures, struct_type_params, module_outline,
+        OutlineKind, OutlineNode, write_module_signatures, ast_fingerprint,
+        module_signature_partial, public_abi_json, IdentifierAllocator, validate_disassembly,
+        disasm_functions_matching, module_constants, ConstValue, struct_storage_size, StorageSize,
+        function_type_param_kinds, disasm_tokens, TokenKind, event_structs, disasm_struct,
+        disasm_and_deps, validate_script_args, internal_call_graph, resource_access,
+        script_signature, disasm, FieldAbi, ParamAbi, extract_signature, compact as compact_source,
+    };
+    use libra::libra_types::transaction::TransactionArgument;
+    use libra::libra_vm::file_format::{
+        Kind, ModuleHandleIndex, SignatureToken, StructFieldInformation, StructHandleIndex,
+        Constant, IdentifierIndex,
+    };
+    use libra::lcs;
+    use libra::bytecode_source_map::source_map::ModuleSourceMap;
+    use libra::move_core_types::language_storage::ModuleId;
+    use libra::move_core_types::language_storage::{CORE_CODE_ADDRESS, StructTag, TypeTag};
+    use libra::move_core_types::identifier::Identifier;
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+    use std::fmt::Debug;
+    use std::fs;
+    use libra::libra_vm::CompiledModule;
+
+    #[test]
+    pub fn test_module_signature() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base.move"),
+                    Some(AccountAddress::new([0x1; 20])),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        for (source, dis) in test_set() {
+            let original = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+            let signature = module_signature(&original).unwrap();
+            assert_eq!(&signature.to_string(), dis);
+
+            let recompiled = compiler.compile(dis, Some(CORE_CODE_ADDRESS)).unwrap();
+            let signature = module_signature(&recompiled).unwrap();
+            assert_eq!(&signature.to_string(), dis);
+
+            // Function bodies are replaced with `abort`,
+            // so only the modules without functions compile back to the same bytecode.
+            if !dis.contains("fun ") {
+                assert_modules_eq(&original, &recompiled);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_module_diff() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let left = compiler
+            .compile("module M { struct A { f: u64 } }", Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        let right = compiler
+            .compile("module M { struct B { f: u64 } }", Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        assert_eq!(
+            module_diff(
+                &CompiledModule::deserialize(&left).unwrap(),
+                &CompiledModule::deserialize(&left).unwrap()
+            ),
+            None
+        );
+        let diff = module_diff(
+            &CompiledModule::deserialize(&left).unwrap(),
+            &CompiledModule::deserialize(&right).unwrap(),
+        )
+        .unwrap();
+        assert!(diff.starts_with("Table 'identifiers' diverged at index"));
+    }
+
+    #[test]
+    pub fn test_specs_as_comments() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun add(a: u64, b: u64): u64 {
+                    a + b
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut specs = BTreeMap::new();
+        specs.insert(
+            "add".to_owned(),
+            "spec fun add {\n    aborts_if false;\n}".to_owned(),
+        );
+        let config = Config::default().with_specs(&specs);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains(
+            "    // spec fun add {\n    //     aborts_if false;\n    // }\n    public fun add("
+        ));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(!signature.contains("spec"));
+    }
+
+    #[test]
+    pub fn test_attributes() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun add(a: u64, b: u64): u64 {
+                    a + b
+                }
+
+                public fun check_add() {
+                    assert(add(1, 2) == 3, 1);
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut attributes = BTreeMap::new();
+        attributes.insert("check_add".to_owned(), vec!["test".to_owned()]);
+        let config = Config::default().with_attributes(&attributes);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains("    #[test]\n    public fun check_add("));
+        assert!(!signature.contains("#[test]\n    public fun add("));
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(!signature.contains("#["));
+    }
+
+    #[test]
+    pub fn test_import_aliases() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let coin = "module Coin { resource struct T { value: u64 } }";
+        let account = "module Account { resource struct T { value: u64 } }";
+        ds.publish_module(compiler.compile(coin, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+        ds.publish_module(compiler.compile(account, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+        let address = AccountAddress::from_hex_literal("0x2").unwrap();
+        ds.publish_module(compiler.compile(coin, Some(address)).unwrap())
+            .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Account;
+                use 0x1::Coin;
+                use 0x2::Coin as Coin2;
+
+                public fun f(_a: Account::T, _c: Coin::T, _c2: Coin2::T) {
+                    abort 1
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        let uses = signature
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("use "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            uses,
+            vec![
+                "use 0x0000000000000000000000000000000000000001::Account;",
+                "use 0x0000000000000000000000000000000000000001::Coin;",
+                "use 0x0000000000000000000000000000000000000002::Coin as OtherCoin1;",
+            ]
+        );
+        assert!(signature.contains(
+            "public fun f(_arg_1: Account::T, _arg_2: Coin::T, _arg_3: OtherCoin1::T) {"
+        ));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_bytecode_listing_imports() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let coin = "
+            module Coin {
+                struct T { value: u64 }
+            }
+        ";
+        ds.publish_module(compiler.compile(coin, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+        let address = AccountAddress::from_hex_literal("0x2").unwrap();
+        ds.publish_module(compiler.compile(coin, Some(address)).unwrap())
+            .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Coin;
+                use 0x2::Coin as Coin2;
+
+                fun id<T>(x: T): T {
+                    x
+                }
+
+                public fun f(c: Coin::T, c2: Coin2::T): (Coin::T, Coin2::T) {
+                    (id<Coin::T>(c), id<Coin2::T>(c2))
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_bytecode_listing(true);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature
+            .contains("use 0x0000000000000000000000000000000000000002::Coin as OtherCoin1;"));
+        assert!(signature.contains("CallGeneric id<Coin::T>\n"));
+        assert!(signature.contains("CallGeneric id<OtherCoin1::T>\n"));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_import_alias_collisions() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        for (source, address) in &[
+            ("module M { struct T<X> { x: X } }", "0x2"),
+            ("module M { struct T { f: u64 } }", "0x3"),
+            ("module OtherM1 { struct T { f: u64 } }", "0x1"),
+        ] {
+            let address = AccountAddress::from_hex_literal(address).unwrap();
+            ds.publish_module(compiler.compile(source, Some(address)).unwrap())
+                .unwrap();
+        }
+
+        let source = "
+            module N {
+                use 0x2::M as M2;
+                use 0x3::M as M3;
+                use 0x1::OtherM1;
+
+                struct S {
+                    a: M2::T<u64>,
+                    b: M3::T,
+                    c: OtherM1::T,
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        let aliases = signature
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("use "))
+            .map(|line| {
+                line.trim_end_matches(';')
+                    .rsplit(|c| c == ' ' || c == ':')
+                    .next()
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(aliases.len(), 3);
+        assert!(signature.contains("T<u64>,"));
+
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_write_to() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct A { f: u64 }
+
+                public fun f(a: A): A {
+                    a
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        for compact in &[false, true] {
+            let config = Config::default().with_compact(*compact);
+            let signature = module_signature_with_configuration(&bytecode, config).unwrap();
+            let mut out = Vec::new();
+            signature.write_to(&mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), signature.to_string());
+        }
+    }
+
+    #[test]
+    pub fn test_indent() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let coin = "
+            module Coin {
+                resource struct T { value: u64 }
+            }
+        ";
+        ds.publish_module(compiler.compile(coin, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Coin;
+
+                struct S { f: u64 }
+
+                public fun f(_c: Coin::T, _s: S) {
+                    abort 1
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_indent(2);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        let module = &signature[signature.find("module M {").unwrap()..];
+        assert_eq!(
+            module,
+            "module M {\n  use 0x0000000000000000000000000000000000000001::Coin;\n  \
+             struct S {\n    f: u64,\n  }\n\n  \
+             public fun f(_arg_1: Coin::T, _arg_2: S) {\n    abort 1\n  }\n\n}\n}\n"
+        );
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        if let StructFieldInformation::Declared(fields) =
+            &mut module.struct_defs[0].field_information
+        {
+            fields[0].signature.0 = SignatureToken::Struct(StructHandleIndex(100));
+        }
+        let signature = extract_signature(&module, Config::default().with_indent(2)).to_string();
+        assert!(signature.contains(
+            "\n  // <error decoding struct 0: struct handle index 100 is out of bounds>\n"
+        ));
+    }
+
+    #[test]
+    pub fn test_bytecode_listing() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun sum(a: u64, b: u64): u64 {
+                    double(a + b) * 3
+                }
+
+                fun double(x: u64): u64 {
+                    x * 2
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_bytecode_listing(true);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        let listing = signature
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("// L"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listing,
+            vec![
+                "// L0: MoveLoc _arg_1",
+                "// L1: LdU64 2",
+                "// L2: Mul",
+                "// L3: Ret",
+                "// L0: MoveLoc _arg_1",
+                "// L1: MoveLoc _arg_2",
+                "// L2: Add",
+                "// L3: Call double",
+                "// L4: LdU64 3",
+                "// L5: Mul",
+                "// L6: Ret",
+            ]
+        );
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(!signature.contains("// L0"));
+    }
+
+    #[test]
+    pub fn test_renames() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct Coin { value: u64 }
+                resource struct Wallet { coins: vector<Coin> }
+
+                public fun pay(coin: Coin, addr: address): Coin acquires Coin {
+                    borrow_global<Coin>(addr).value;
+                    coin
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut renames = BTreeMap::new();
+        renames.insert("Coin".to_owned(), "Token".to_owned());
+        renames.insert("pay".to_owned(), "send".to_owned());
+        let config = Config::default().with_renames(&renames);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(!signature.contains("Coin"));
+        assert!(!signature.contains("pay"));
+        assert!(signature.contains("resource struct Token {"));
+        assert!(signature.contains("coins: vector<Token>"));
+        assert!(signature
+            .contains("public fun send(_arg_1: Token, _arg_2: address): Token acquires Token {"));
+        assert!(signature.contains("borrow_global<Token>"));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_renames_errors() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct Coin { value: u64 }
+                resource struct Wallet { coins: vector<Coin> }
+
+                public fun pay(coin: Coin): Coin {
+                    coin
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let rename_err = |bytecode: &[u8], renames: &[(&str, &str)]| {
+            let renames = renames
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect::<BTreeMap<_, _>>();
+            let config = Config::default().with_renames(&renames);
+            module_signature_with_configuration(bytecode, config)
+                .err()
+                .map(|err| err.to_string())
+        };
+
+        assert_eq!(
+            rename_err(&bytecode, &[("Coin", "1Token")]).unwrap(),
+            "New name '1Token' of 'Coin' is not a valid identifier."
+        );
+        assert_eq!(
+            rename_err(&bytecode, &[("Coin", "pay")]).unwrap(),
+            "New name 'pay' of 'Coin' is already taken by the module."
+        );
+        assert_eq!(
+            rename_err(&bytecode, &[("Coin", "Token"), ("Wallet", "Token")]).unwrap(),
+            "New name 'Token' is given to more than one definition."
+        );
+        assert_eq!(
+            rename_err(&bytecode, &[("Coin", "Wallet"), ("Wallet", "Coin")]),
+            None
+        );
+
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let coin = module
+            .identifiers
+            .iter()
+            .position(|ident| ident.as_str() == "Coin")
+            .unwrap();
+        let pay = module.function_defs[0].function;
+        module.function_handles[pay.0 as usize].name = IdentifierIndex(coin as u16);
+        let mut shared = vec![];
+        module.serialize(&mut shared).unwrap();
+        assert_eq!(
+            rename_err(&shared, &[("Coin", "Token")]).unwrap(),
+            "Rename of 'Coin' is ambiguous: the module has a struct and a function with this name."
+        );
+    }
+
+    #[test]
+    pub fn test_source_map_names() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun transfer<Coin: copyable>(to: address, amount: Coin): Coin {
+                    amount
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let module = CompiledModule::deserialize(&bytecode).unwrap();
+
+        let mut source_map = ModuleSourceMap::dummy_from_module(&module, ()).unwrap();
+        let function = source_map.function_map.get_mut(&0).unwrap();
+        function.type_parameters = vec![("Coin".to_owned(), ())];
+        function.locals = vec![("to".to_owned(), ()), ("amount".to_owned(), ())];
+
+        let config = Config::default().with_source_map(&source_map);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature
+            .contains("public fun transfer<Coin: copyable>(to: address, amount: Coin): Coin {"));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(signature.contains(
+            "public fun transfer<__G_1: copyable>(_arg_1: address, _arg_2: __G_1): __G_1 {"
+        ));
+    }
+
+    #[test]
+    pub fn test_source_map_names_collisions() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Box<T> { value: T }
+                struct T1 { f: u64 }
+
+                public fun f<T: copyable>(_a: T, _b: u64, _c: u64, _d: u64): T {
+                    _a
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let module = CompiledModule::deserialize(&bytecode).unwrap();
+
+        let mut source_map = ModuleSourceMap::dummy_from_module(&module, ()).unwrap();
+        source_map.struct_map.get_mut(&0).unwrap().type_parameters = vec![("T1".to_owned(), ())];
+        let function = source_map.function_map.get_mut(&0).unwrap();
+        function.type_parameters = vec![("Coin".to_owned(), ())];
+        function.locals = vec![
+            ("x#0#0".to_owned(), ()),
+            ("_arg_3".to_owned(), ()),
+            ("Coin".to_owned(), ()),
+            ("amount".to_owned(), ()),
+        ];
+
+        let config = Config::default().with_source_map(&source_map);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains("struct Box<__G_1> {\n        value: __G_1,\n    }"));
+        assert!(signature.contains(
+            "public fun f<Coin: copyable>(_arg_1: Coin, _arg_2: u64, _arg_3: u64, amount: u64): Coin {"
+        ));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        source_map.struct_map.get_mut(&0).unwrap().type_parameters = vec![("Item".to_owned(), ())];
+        let config = Config::default().with_source_map(&source_map);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains("struct Box<Item> {\n        value: Item,\n    }"));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_unit_and_tuple_returns() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun nothing() {
+                }
+
+                public fun unit(): () {
+                    ()
+                }
+
+                public fun pair(a: u64): (u64, bool) {
+                    (a, true)
+                }
+
+                native fun native_unit(a: u64);
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(signature.contains("    public fun nothing() {"));
+        assert!(signature.contains("    public fun unit() {"));
+        assert!(signature.contains("    public fun pair(_arg_1: u64): (u64, bool) {"));
+        assert!(signature.contains("    native fun native_unit(_arg_1: u64);"));
+        assert!(!signature.contains(": ()"));
+
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        assert_eq!(
+            module_signature(&recompiled).unwrap().to_string(),
+            signature
+        );
+    }
+
+    #[test]
+    pub fn test_strict_mode() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let strict = || Config::default().with_strict(true);
+
+        let source = "
+            module M {
+                resource struct T { f: u64 }
+                native public fun create(): T;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature_with_configuration(&bytecode, strict()).unwrap();
+        assert_eq!(
+            signature.to_string(),
+            module_signature(&bytecode).unwrap().to_string()
+        );
+
+        let source = "
+            module M {
+                public fun zero(): u64 {
+                    0
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        module_signature(&bytecode).unwrap();
+        let err = module_signature_with_configuration(&bytecode, strict())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Body of the function 'zero' can't be disassembled."
+        );
+    }
+
+    #[test]
+    pub fn test_struct_accessors() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct R { value: u64, owner: address }
+                struct S { flag: bool }
+                native struct N;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap();
+
+        let structs = signature
+            .structs()
+            .iter()
+            .map(|s| (s.name(), s.is_resource(), s.is_native()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            structs,
+            vec![("N", false, true), ("R", true, false), ("S", false, false)]
+        );
+
+        let fields = signature.structs()[1]
+            .fields()
+            .iter()
+            .map(|f| (f.name(), f.f_type().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![("value", "u64".to_owned()), ("owner", "address".to_owned())]
+        );
+    }
+
+    #[test]
+    pub fn test_compact_mode() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct T<V: copyable> { g: V, h: vector<u8> }
+                native public fun create<V: copyable>(v: V): T<V>;
+                public fun value(t: &T<u64>): u64 acquires T {
+                    let _ = borrow_global<T<u64>>(0x1);
+                    t.g
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let pretty = module_signature(&bytecode).unwrap().to_string();
+        let compact =
+            module_signature_with_configuration(&bytecode, Config::default().with_compact(true))
+                .unwrap()
+                .to_string();
+        assert_eq!(compact.trim_end().lines().count(), 1);
+
+        assert_eq!(
+            compiler.compile(&pretty, Some(CORE_CODE_ADDRESS)).unwrap(),
+            compiler.compile(&compact, Some(CORE_CODE_ADDRESS)).unwrap()
+        );
+
+        let config = Config::default()
+            .with_compact(true)
+            .with_header("generated */ struct Broken {}\n// nested /* open");
+        let compact = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(
+            compact.starts_with("/* generated * / struct Broken {} */ /* // nested / * open */ ")
+        );
+        assert_eq!(
+            compiler.compile(&pretty, Some(CORE_CODE_ADDRESS)).unwrap(),
+            compiler.compile(&compact, Some(CORE_CODE_ADDRESS)).unwrap()
+        );
+
+        assert_eq!(
+            compact_source("    abort 1 // trailing */ comment\n    }\n"),
+            "abort 1 /* trailing * / comment */ }"
+        );
+    }
+
+    #[test]
+    pub fn test_type_tags() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Coin { value: u64 }
+                struct Holder { wrapped: Wrapper<Coin> }
+                struct Wrapper<T> { value: T, coin: Coin, bytes: vector<u8> }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap();
+        let structs = signature.structs();
+
+        let struct_tag = |name: &str, type_params: Vec<TypeTag>| StructTag {
+            address: CORE_CODE_ADDRESS,
+            module: Identifier::new("M").unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params,
+        };
+        let coin = TypeTag::Struct(struct_tag("Coin", vec![]));
+
+        assert_eq!(
+            structs[0].fields()[0].f_type().to_type_tag(),
+            Some(TypeTag::U64)
+        );
+        assert_eq!(
+            structs[1].fields()[0].f_type().to_type_tag(),
+            Some(TypeTag::Struct(struct_tag("Wrapper", vec![coin.clone()])))
+        );
+
+        let wrapper = structs[2].fields();
+        assert_eq!(wrapper[0].f_type().to_type_tag(), None);
+        assert_eq!(wrapper[1].f_type().to_type_tag(), Some(coin));
+        assert_eq!(
+            wrapper[2].f_type().to_type_tag(),
+            Some(TypeTag::Vector(Box::new(TypeTag::U8)))
+        );
+    }
+
+    #[test]
+    pub fn test_script_arg_types() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            script {
+                fun main(_a: u64, _b: address, _c: vector<u8>) {}
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let args = script_arg_types(&bytecode)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(args, vec!["u64", "address", "vector<u8>"]);
+        assert_eq!(script_type_params_count(&bytecode).unwrap(), 0);
+
+        let source = "
+            script {
+                fun main<T: copyable>(_a: &signer) {}
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let args = script_arg_types(&bytecode)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(args, vec!["&signer"]);
+        assert_eq!(script_type_params_count(&bytecode).unwrap(), 1);
+    }
+
+    #[test]
+    pub fn test_script_signature() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let coin = "
+            module Coin {
+                resource struct T { value: u64 }
+            }
+        ";
+        ds.publish_module(compiler.compile(coin, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+
+        let source = "
+            script {
+                use 0x1::Coin;
+
+                fun main<Token: copyable>(_a: &signer, _b: u64, _c: &Coin::T, _d: Token) {}
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = script_signature(&bytecode).unwrap().to_string();
+        assert!(signature.starts_with("script {\n"));
+        assert!(signature.contains(&format!("    use 0x{}::Coin;", CORE_CODE_ADDRESS)));
+        assert!(signature.contains(
+            "fun main<__G_1: copyable>(_arg_1: &signer, _arg_2: u64, _arg_3: &Coin::T, _arg_4: __G_1) {"
+        ));
+        assert!(!signature.contains("public"));
+        assert_eq!(disasm(&bytecode).unwrap(), signature);
+
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        let arg_types = |bytecode: &[u8]| {
+            script_arg_types(bytecode)
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(arg_types(&recompiled), arg_types(&bytecode));
+        assert_eq!(script_type_params_count(&recompiled).unwrap(), 1);
+
+        let module = compiler.compile(coin, Some(CORE_CODE_ADDRESS)).unwrap();
+        assert!(script_signature(&module).is_err());
+        assert_eq!(
+            disasm(&module).unwrap(),
+            module_signature(&module).unwrap().to_string()
+        );
+
+        let err = disasm(&bytecode[..bytecode.len() / 2])
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Bytecode is neither a script ("));
+        assert!(err.contains(") nor a module ("));
+    }
+
+    #[test]
+    pub fn test_validate_script_args() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            script {
+                fun main(_s: &signer, _a: u64, _b: address, _c: vector<u8>) {}
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let args = vec![
+            TransactionArgument::U64(10),
+            TransactionArgument::Address(CORE_CODE_ADDRESS),
+            TransactionArgument::U8Vector(vec![1, 2]),
+        ];
+        validate_script_args(&bytecode, &args).unwrap();
+
+        let err = validate_script_args(&bytecode, &args[..2]).unwrap_err();
+        assert_eq!(err.to_string(), "Expected 3 script arguments, got 2.");
+
+        let args = vec![
+            TransactionArgument::U64(10),
+            TransactionArgument::Bool(true),
+            TransactionArgument::U8Vector(vec![1, 2]),
+        ];
+        let err = validate_script_args(&bytecode, &args).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Script argument 1 has type bool, expected address."
+        );
+    }
+
+    #[test]
+    pub fn test_reachable_module_signatures() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let sources = [
+            "module B { struct T { f: u64 } }",
+            "module A { use 0x1::B; struct T { b: B::T } }",
+            "module M { use 0x1::A; use 0x1::B; struct T { a: A::T, b: B::T } }",
+        ];
+        for source in &sources {
+            ds.publish_module(compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap())
+                .unwrap();
+        }
+
+        let id = |name: &str| ModuleId::new(CORE_CODE_ADDRESS, Identifier::new(name).unwrap());
+        let source = StateViewLoader::new(ds);
+        let signatures = reachable_module_signatures(&source, &id("M")).unwrap();
+        assert_eq!(
+            signatures.keys().cloned().collect::<Vec<_>>(),
+            vec![id("A"), id("B"), id("M")]
+        );
+        for (id, signature) in signatures {
+            assert!(signature.contains(&format!("module {} {{", id.name())));
+        }
+
+        assert!(reachable_module_signatures(&source, &id("Unknown")).is_err());
+    }
+
+    #[test]
+    pub fn test_in_memory_source() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let id = |name: &str| ModuleId::new(CORE_CODE_ADDRESS, Identifier::new(name).unwrap());
+
+        let mut source = InMemorySource::new();
+        let modules = [
+            ("B", "module B { struct T { f: u64 } }"),
+            ("A", "module A { use 0x1::B; struct T { b: B::T } }"),
+        ];
+        for (name, code) in &modules {
+            let bytecode = compiler.compile(code, Some(CORE_CODE_ADDRESS)).unwrap();
+            ds.publish_module(bytecode.clone()).unwrap();
+            source.add(id(name), bytecode);
+        }
+        let bytecode = compiler
+            .compile(
+                "module M { use 0x1::A; struct T { a: A::T } }",
+                Some(CORE_CODE_ADDRESS),
+            )
+            .unwrap();
+        source.add(id("M"), bytecode.clone());
+
+        let deps = extract_bytecode_tree(&source, &bytecode).unwrap();
+        assert_eq!(
+            deps.keys().cloned().collect::<Vec<_>>(),
+            vec![id("A"), id("B")]
+        );
+
+        let signatures = reachable_module_signatures(&source, &id("M")).unwrap();
+        assert_eq!(
+            signatures.keys().cloned().collect::<Vec<_>>(),
+            vec![id("A"), id("B"), id("M")]
+        );
+        assert_eq!(
+            signatures[&id("M")],
+            module_signature(&bytecode).unwrap().to_string()
+        );
+
+        assert!(extract_bytecode_tree(&InMemorySource::new(), &bytecode).is_err());
+    }
+
+    #[test]
+    pub fn test_uses_from_same_address() {
+        // The Move dialect doesn't support `use 0x1::{A, B};` grouping,
+        // so modules imported from the same address are used one by one.
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        for source in &[
+            "module A { struct T { f: u64 } }",
+            "module B { struct T { f: u64 } }",
+        ] {
+            ds.publish_module(compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap())
+                .unwrap();
+        }
+
+        let source = "module M { use 0x1::A; use 0x1::B; struct T { a: A::T, b: B::T } }";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(signature.contains(&format!(
+            "    use 0x{address}::A;\n    use 0x{address}::B;\n",
+            address = CORE_CODE_ADDRESS
+        )));
+
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        assert_modules_eq(&bytecode, &recompiled);
+    }
+
+    #[test]
+    pub fn test_type_param_kinds() {
+        let compiler = Compiler::new(MockDataSource::new());
+        for constraint in &["", ": resource", ": copyable"] {
+            let source = format!(
+                "module M {{
+                    resource struct S<T{c}> {{ f: T }}
+                    native public fun n<T{c}>(t: &T);
+                    public fun f<T{c}>(_t: &T) {{}}
+                }}",
+                c = constraint
+            );
+            let bytecode = compiler.compile(&source, Some(CORE_CODE_ADDRESS)).unwrap();
+            let signature = module_signature(&bytecode).unwrap().to_string();
+            for expected in &[
+                format!("resource struct S<__G_1{}> {{", constraint),
+                format!("native public fun n<__G_1{}>(_arg_1: &__G_1);", constraint),
+                format!("public fun f<__G_1{}>(_arg_1: &__G_1) {{", constraint),
+            ] {
+                assert!(signature.contains(expected), "{}", signature);
+            }
+
+            let recompiled = compiler
+                .compile(&signature, Some(CORE_CODE_ADDRESS))
+                .unwrap();
+            assert_eq!(
+                module_signature(&recompiled).unwrap().to_string(),
+                signature
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_struct_type_params() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Plain { f: u64 }
+                struct Pair<K: copyable, V> { k: K, v: V }
+                resource struct Pool<R: resource> { r: R }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        assert_eq!(
+            struct_type_params(&bytecode).unwrap(),
+            vec![
+                ("Pair".to_owned(), vec![Kind::Copyable, Kind::All]),
+                ("Plain".to_owned(), vec![]),
+                ("Pool".to_owned(), vec![Kind::Resource]),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_non_ascii_identifier() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "module M { struct NameAb { f: u64 } }";
+        let mut bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let name = bytecode
+            .windows(6)
+            .position(|window| window == b"NameAb")
+            .unwrap();
+        // Same length in bytes, so the table offsets stay valid.
+        bytecode[name + 4..name + 6].copy_from_slice("é".as_bytes());
+
+        // Identifiers are validated on deserialization, so such a module is never rendered.
+        assert!(module_signature(&bytecode).is_err());
+        assert!(module_signature_partial(&bytecode).is_err());
+    }
+
+    #[test]
+    pub fn test_disasm_and_deps() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile("module A { struct T { f: u64 } }", Some(CORE_CODE_ADDRESS))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::A;
+
+                struct T { a: A::T }
+
+                public fun f(t: T): T {
+                    t
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let (signature, deps) = disasm_and_deps(&bytecode).unwrap();
+        assert_eq!(signature, module_signature(&bytecode).unwrap().to_string());
+        assert_eq!(deps, extract_from_bytecode(&bytecode).unwrap());
+        assert_eq!(deps.len(), 1);
+    }
+
+    #[test]
+    pub fn test_disasm_struct() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let dep = "
+            module Coins {
+                struct Coin { value: u64 }
+            }
+        ";
+        ds.publish_module(compiler.compile(dep, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Coins;
+
+                struct Plain { f: u64 }
+                resource struct Vault<T> { coin: Coins::Coin, t: T }
+                resource struct Other { plain: Plain }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        assert_eq!(
+            disasm_struct(&bytecode, "Vault").unwrap(),
+            format!(
+                "use 0x{}::Coins;\n\nresource struct Vault<__G_1> {{\n    coin: Coins::Coin,\n    t: __G_1,\n}}\n",
+                CORE_CODE_ADDRESS
             )
+        );
+        assert_eq!(
+            disasm_struct(&bytecode, "Other").unwrap(),
+            "resource struct Other {\n    plain: Plain,\n}\n"
+        );
+        assert!(disasm_struct(&bytecode, "Missing").is_err());
+    }
+
+    #[test]
+    pub fn test_internal_call_graph() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun a(): u64 {
+                    b() + 1
+                }
+
+                fun b(): u64 {
+                    c<u64>(2) + c<bool>(true)
+                }
+
+                fun c<T>(_t: T): u64 {
+                    3
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let graph = internal_call_graph(&bytecode).unwrap();
+
+        let callees = |name: &str| {
+            let mut callees = graph[name].iter().cloned().collect::<Vec<_>>();
+            callees.sort();
+            callees
+        };
+        assert_eq!(graph.len(), 3);
+        assert_eq!(callees("a"), vec!["b"]);
+        assert_eq!(callees("b"), vec!["c"]);
+        assert!(callees("c").is_empty());
+    }
+
+    #[test]
+    pub fn test_resource_access() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct Counter { value: u64 }
+                resource struct Limit<T> { value: T }
+                resource struct Log { count: u64 }
+
+                public fun inc(account: &signer, addr: address): u64 acquires Counter, Limit, Log {
+                    let limit = borrow_global<Limit<u64>>(addr).value;
+                    let counter = borrow_global_mut<Counter>(addr);
+                    counter.value = counter.value + 1;
+                    if (!exists<Log>(addr)) {
+                        move_to<Log>(account, Log { count: 0 });
+                    };
+                    limit - counter.value
+                }
+
+                public fun value(addr: address): u64 acquires Counter {
+                    borrow_global<Counter>(addr).value
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let access = resource_access(&bytecode, "inc").unwrap();
+        assert_eq!(
+            access.reads.into_iter().collect::<Vec<_>>(),
+            vec!["Limit", "Log"]
+        );
+        assert_eq!(
+            access.writes.into_iter().collect::<Vec<_>>(),
+            vec!["Counter", "Log"]
+        );
+
+        let access = resource_access(&bytecode, "value").unwrap();
+        assert_eq!(
+            access.reads.into_iter().collect::<Vec<_>>(),
+            vec!["Counter"]
+        );
+        assert!(access.writes.is_empty());
+
+        assert!(resource_access(&bytecode, "unknown").is_err());
+    }
+
+    #[test]
+    pub fn test_event_structs() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let event = "
+            module Event {
+                resource struct EventHandle<T: copyable> { counter: u64 }
+
+                native public fun emit_event<T: copyable>(handle: &mut EventHandle<T>, msg: T);
+            }
+        ";
+        ds.publish_module(compiler.compile(event, Some(CORE_CODE_ADDRESS)).unwrap())
+            .unwrap();
+
+        let source = "
+            module Bank {
+                use 0x1::Event;
+
+                struct Deposited { amount: u64 }
+                struct Withdrawn { amount: u64 }
+
+                resource struct Events {
+                    deposited: Event::EventHandle<Deposited>,
+                    withdrawn: Event::EventHandle<Withdrawn>,
+                }
+
+                public fun deposit(events: &mut Events, amount: u64) {
+                    Event::emit_event(&mut events.deposited, Deposited { amount })
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let events = event_structs(&bytecode).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "Deposited");
+        assert_eq!(events[0].fields()[0].name(), "amount");
+        assert_eq!(events[0].fields()[0].f_type().to_string(), "u64");
+    }
+
+    #[test]
+    pub fn test_function_type_param_kinds() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct Vault<T> { t: T }
+
+                public fun deposit<T: resource, M: copyable, A>(account: &signer, t: T, m: M, a: A) {
+                    abort 1
+                }
+
+                public fun total(): u64 {
+                    0
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        assert_eq!(
+            function_type_param_kinds(&bytecode, "deposit").unwrap(),
+            vec![Kind::Resource, Kind::Copyable, Kind::All]
+        );
+        assert_eq!(
+            function_type_param_kinds(&bytecode, "total").unwrap(),
+            vec![]
+        );
+        assert!(function_type_param_kinds(&bytecode, "withdraw").is_err());
+    }
+
+    #[test]
+    pub fn test_struct_storage_size() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Coin { value: u128 }
+                resource struct Balance { coin: Coin, frozen: bool, owner: address }
+                resource struct Events<T> { count: u64, handle: vector<u8>, last: T }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        assert_eq!(
+            struct_storage_size(&bytecode, "Balance").unwrap(),
+            StorageSize {
+                fixed: 16 + 1 + 20,
+                variable: false,
+            }
+        );
+        assert_eq!(
+            struct_storage_size(&bytecode, "Events").unwrap(),
+            StorageSize {
+                fixed: 8,
+                variable: true,
+            }
+        );
+        assert!(struct_storage_size(&bytecode, "Unknown").is_err());
+    }
+
+    #[test]
+    pub fn test_struct_order() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Zebra { apple: Apple }
+                struct Apple { f: u64 }
+                resource struct Mango { zebra: Zebra }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let names = |bytecode: &[u8]| {
+            struct_type_params(bytecode)
+                .unwrap()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&bytecode), vec!["Apple", "Mango", "Zebra"]);
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        let apple = signature.find("struct Apple").unwrap();
+        let mango = signature.find("struct Mango").unwrap();
+        let zebra = signature.find("struct Zebra").unwrap();
+        assert!(apple < mango && mango < zebra);
+
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        assert_eq!(names(&recompiled), names(&bytecode));
+        assert_eq!(
+            module_signature(&recompiled).unwrap().to_string(),
+            signature
+        );
+    }
+
+    #[test]
+    pub fn test_visibility_comments() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T { f: u64 }
+
+                public fun get(t: &T): u64 {
+                    t.f
+                }
+
+                fun set(t: &mut T, f: u64) {
+                    t.f = f
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_visibility_comments(true);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains("    // public\n    struct T {"));
+        assert!(signature.contains("    // public\n    public fun get("));
+        assert!(signature.contains("    // private\n    fun set("));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+
+        let signature = module_signature(&bytecode).unwrap().to_string();
+        assert!(!signature.contains("// public") && !signature.contains("// private"));
+    }
+
+    #[test]
+    pub fn test_handle_indices() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct A { f: u64 }
+                resource struct B { a: A }
+                native public fun get(): B;
+                native fun put(b: B);
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature_with_configuration(
+            &bytecode,
+            Config::default().with_handle_indices(true),
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(signature.contains("    // struct_handle #0\n    struct A {"));
+        assert!(signature.contains("    // struct_handle #1\n    resource struct B {"));
+        assert!(signature.contains("    // function_handle #0\n    native public fun get("));
+        assert!(signature.contains("    // function_handle #1\n    native fun put("));
+
+        let plain = module_signature(&bytecode).unwrap().to_string();
+        assert!(!plain.contains("_handle #"));
+        assert_eq!(
+            compiler
+                .compile(&signature, Some(CORE_CODE_ADDRESS))
+                .unwrap(),
+            compiler.compile(&plain, Some(CORE_CODE_ADDRESS)).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_disasm_tokens() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct T { owner: address, value: u64 }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let text = module_signature(&bytecode).unwrap().to_string();
+        let tokens = disasm_tokens(&bytecode).unwrap();
+
+        for token in &tokens {
+            assert_eq!(
+                &text[token.offset()..token.offset() + token.text().len()],
+                token.text()
+            );
         }
+
+        let kinds = tokens
+            .iter()
+            .map(|token| (token.text(), token.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds[0], ("address", TokenKind::Keyword));
+        assert_eq!(kinds[1].1, TokenKind::Literal);
+        assert_eq!(kinds[2], ("{", TokenKind::Punctuation));
+        assert_eq!(
+            &kinds[3..8],
+            &[
+                ("module", TokenKind::Keyword),
+                ("M", TokenKind::Identifier),
+                ("{", TokenKind::Punctuation),
+                ("resource", TokenKind::Keyword),
+                ("struct", TokenKind::Keyword),
+            ]
+        );
+        assert!(kinds.contains(&("owner", TokenKind::Identifier)));
+        assert!(kinds.contains(&("address", TokenKind::Type)));
+        assert!(kinds.contains(&("u64", TokenKind::Type)));
     }
-}
 
-struct Structs {
-    structs: Vec<Struct>,
-}
+    #[test]
+    pub fn test_disasm_tokens_types() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct Box<T> { value: T }
+
+                public fun f(_v: &mut vector<u8>, b: Box<u64>): Box<u64> {
+                    b
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let tokens = disasm_tokens(&bytecode).unwrap();
+        let kinds = tokens
+            .iter()
+            .map(|token| (token.text(), token.kind()))
+            .collect::<Vec<_>>();
+
+        for (text, kind) in &[
+            ("Box", TokenKind::Identifier),
+            ("__G_1", TokenKind::Identifier),
+            ("value", TokenKind::Identifier),
+            ("f", TokenKind::Identifier),
+            ("_arg_1", TokenKind::Identifier),
+            ("mut", TokenKind::Keyword),
+            ("abort", TokenKind::Keyword),
+            ("vector", TokenKind::Type),
+            ("u8", TokenKind::Type),
+            ("u64", TokenKind::Type),
+            ("1", TokenKind::Literal),
+        ] {
+            assert!(
+                kinds.contains(&(*text, *kind)),
+                "{} is not {:?}",
+                text,
+                kind
+            );
+        }
+
+        // Only the primitive types are classified as types, struct names are identifiers.
+        let types = kinds
+            .iter()
+            .filter(|(_, kind)| *kind == TokenKind::Type)
+            .map(|(text, _)| *text)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            types,
+            vec!["u64", "u8", "vector"]
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+        );
+    }
+
+    #[test]
+    pub fn test_module_outline() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base.move"),
+                    Some(AccountAddress::new([0x1; 20])),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        let bytecode = compiler
+            .compile(
+                include_str!("../../tests/resources/disassembler/module_with_structs.move"),
+                Some(CORE_CODE_ADDRESS),
+            )
+            .unwrap();
+        let outline = module_outline(&bytecode).unwrap();
+        assert_eq!(outline.name(), "Foo");
+        assert_eq!(outline.kind(), OutlineKind::Module);
+
+        let structs = outline
+            .children()
+            .iter()
+            .map(|node| {
+                assert_eq!(node.kind(), OutlineKind::Struct);
+                node.name()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            structs,
+            vec![
+                "Bar",
+                "BarNative",
+                "G",
+                "GBase",
+                "GBase2",
+                "Pool",
+                "Pool1",
+                "R",
+                "T",
+                "Vec"
+            ]
+        );
+
+        let field = |name: &str| OutlineNode::new(name, OutlineKind::Field, vec![]);
+        assert_eq!(
+            outline.children()[0].children(),
+            &[field("key"), field("value")]
+        );
+        assert!(outline.children()[1].children().is_empty());
+
+        let source = "
+            module M {
+                resource struct R { v: u64 }
+                public fun value(r: &R, scale: u64): u64 acquires R {
+                    let _ = borrow_global<R>(0x1);
+                    r.v * scale
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let param = |name: &str| OutlineNode::new(name, OutlineKind::Param, vec![]);
+        assert_eq!(
+            module_outline(&bytecode).unwrap(),
+            OutlineNode::new(
+                "M",
+                OutlineKind::Module,
+                vec![
+                    OutlineNode::new("R", OutlineKind::Struct, vec![field("v")]),
+                    OutlineNode::new(
+                        "value",
+                        OutlineKind::Function,
+                        vec![param("_arg_1"), param("_arg_2")]
+                    ),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_aliased_self_handle() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Base;
+                struct S { f: u64 }
+                resource struct T { s: S, b: Base::Test1 }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        // Refer to the struct `S` through a copy of the self module handle.
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let self_handle = module.module_handles[0].clone();
+        module.module_handles.push(self_handle);
+        let alias = ModuleHandleIndex((module.module_handles.len() - 1) as u16);
+        let identifiers = &module.identifiers;
+        let handle = module
+            .struct_handles
+            .iter_mut()
+            .find(|handle| identifiers[handle.name.0 as usize].as_str() == "S")
+            .unwrap();
+        handle.module = alias;
+        let mut aliased = vec![];
+        module.serialize(&mut aliased).unwrap();
+
+        let signature = module_signature(&aliased).unwrap().to_string();
+        assert!(signature.contains("        s: S,\n"));
+        assert!(signature.contains("        b: Base::Test1,\n"));
+        assert!(!signature.contains("::M"));
+        assert_eq!(signature, module_signature(&bytecode).unwrap().to_string());
+    }
+
+    #[test]
+    pub fn test_write_module_signatures() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let a = compiler
+            .compile("module A { struct T { f: u64 } }", Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        let b = compiler
+            .compile(
+                "module B { native public fun f(); }",
+                Some(AccountAddress::new([0x2; 20])),
+            )
+            .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let out_dir = dir.path().join("package");
+        let written = write_module_signatures(&[a.clone(), vec![0x1, 0x2], b.clone()], &out_dir);
+        assert_eq!(written.len(), 3);
+        assert!(written[1].is_err());
+
+        let path = written[0].as_ref().unwrap();
+        assert_eq!(
+            path,
+            &out_dir.join(CORE_CODE_ADDRESS.to_string()).join("A.move")
+        );
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            module_signature(&a).unwrap().to_string()
+        );
+
+        let path = written[2].as_ref().unwrap();
+        assert_eq!(
+            path,
+            &out_dir
+                .join(AccountAddress::new([0x2; 20]).to_string())
+                .join("B.move")
+        );
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            module_signature(&b).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    pub fn test_repeated_external_struct() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Base;
+                resource struct T { a: Base::Test1, b: vector<Base::Test1> }
+                native public fun swap(t: Base::Test1): (Base::Test1, T);
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = module_signature(&bytecode).unwrap().to_string();
+
+        // Struct aliases can't be declared, so every reference goes through the single module import.
+        assert_eq!(signature.matches("use ").count(), 1);
+        assert!(signature.contains(&format!("    use 0x{}::Base;\n", CORE_CODE_ADDRESS)));
+        assert_eq!(signature.matches("Base::Test1").count(), 4);
+
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        assert_eq!(
+            module_signature(&recompiled).unwrap().to_string(),
+            signature
+        );
+    }
+
+    #[test]
+    pub fn test_duplicate_names() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct A { f: u64 }
+                struct B { f: bool }
+                native public fun f(): u64;
+                native public fun g(): bool;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let name_index = |name: &str| {
+            module
+                .identifiers
+                .iter()
+                .position(|ident| ident.as_str() == name)
+                .unwrap() as u16
+        };
+        let (a, b_name, f) = (name_index("A"), name_index("B"), name_index("f"));
+        let b = module.struct_defs[1].struct_handle.0 as usize;
+        module.struct_handles[b].name.0 = a;
+
+        let mut duplicate_structs = vec![];
+        module.serialize(&mut duplicate_structs).unwrap();
+
+        let g = module.function_defs[1].function.0 as usize;
+        module.function_handles[g].name.0 = f;
+        let mut duplicate_functions = vec![];
+        module.serialize(&mut duplicate_functions).unwrap();
+
+        let signature = module_signature(&duplicate_functions).unwrap().to_string();
+        assert!(signature
+            .contains("    // duplicate struct name 'A'\n    struct A {\n        f: bool,"));
+        assert!(signature
+            .contains("    // duplicate function name 'f'\n    native public fun f(): bool;"));
+
+        let strict = || Config::default().with_strict(true);
+        let err = module_signature_with_configuration(&duplicate_structs, strict())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Duplicate struct name 'A'.");
+        let err = module_signature_with_configuration(&duplicate_functions, strict())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Duplicate struct name 'A'.");
+
+        module.struct_handles[b].name.0 = b_name;
+        let mut duplicate_functions = vec![];
+        module.serialize(&mut duplicate_functions).unwrap();
+        let err = module_signature_with_configuration(&duplicate_functions, strict())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Duplicate function name 'f'.");
+    }
+
+    #[test]
+    pub fn test_ast_fingerprint() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        let fingerprint = |source: &str| {
+            let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+            ast_fingerprint(&bytecode).unwrap()
+        };
+
+        let original = fingerprint(
+            "
+            module M {
+                use 0x1::Base;
+                resource struct T { a: Base::Test1, b: u64 }
+                native public fun get(): T;
+            }
+            ",
+        );
+        let reformatted = fingerprint(
+            "module M { use 0x1::Base as OtherBase; resource struct T {
+                a: OtherBase::Test1,
+                b: u64,
+            } native public fun get(): T; }",
+        );
+        assert_eq!(original, reformatted);
+
+        let disassembled = fingerprint(
+            &module_signature(
+                &compiler
+                    .compile(
+                        "module M { use 0x1::Base; resource struct T { a: Base::Test1, b: u64 } native public fun get(): T; }",
+                        Some(CORE_CODE_ADDRESS),
+                    )
+                    .unwrap(),
+            )
+            .unwrap()
+            .to_string(),
+        );
+        assert_eq!(original, disassembled);
+
+        let extended = fingerprint(
+            "
+            module M {
+                use 0x1::Base;
+                resource struct T { a: Base::Test1, b: u64, c: bool }
+                native public fun get(): T;
+            }
+            ",
+        );
+        assert_ne!(original, extended);
+    }
+
+    #[test]
+    pub fn test_partial_signature() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct A { f: u64 }
+                struct B { f: bool }
+                struct C { a: A }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let (signature, errors) = module_signature_partial(&bytecode).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(
+            signature.to_string(),
+            module_signature(&bytecode).unwrap().to_string()
+        );
+
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        if let StructFieldInformation::Declared(fields) =
+            &mut module.struct_defs[1].field_information
+        {
+            fields[0].signature.0 = SignatureToken::Struct(StructHandleIndex(100));
+        }
+        let mut broken = vec![];
+        module.serialize(&mut broken).unwrap();
+        assert!(module_signature(&broken).is_err());
+
+        let (signature, errors) = module_signature_partial(&broken).unwrap();
+        assert_eq!(
+            errors,
+            vec!["error decoding struct 1: struct handle index 100 is out of bounds".to_owned()]
+        );
+        let structs = signature
+            .structs()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<_>>();
+        assert_eq!(structs, vec!["A", "C"]);
+
+        let signature = signature.to_string();
+        assert!(signature.contains(
+            "    // <error decoding struct 1: struct handle index 100 is out of bounds>\n"
+        ));
+        assert!(signature.contains("    struct A {\n        f: u64,\n    }"));
+        assert!(signature.contains("    struct C {\n        a: A,\n    }"));
+    }
+
+    #[test]
+    pub fn test_module_abi() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T { a: u64, b: vector<address> }
+                native public fun new(a: u64): T;
+                native fun check(s: &signer, t: &T): bool;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let abi = module_signature(&bytecode).unwrap().abi();
+
+        assert_eq!(abi.address, format!("0x{}", CORE_CODE_ADDRESS));
+        assert_eq!(abi.name, "M");
+        assert_eq!(abi.structs.len(), 1);
+        let t = &abi.structs[0];
+        assert_eq!(t.name, "T");
+        assert!(!t.resource);
+        assert_eq!(
+            t.fields,
+            vec![
+                FieldAbi {
+                    name: "a".to_owned(),
+                    f_type: "u64".to_owned(),
+                },
+                FieldAbi {
+                    name: "b".to_owned(),
+                    f_type: "vector<address>".to_owned(),
+                },
+            ]
+        );
+
+        let t_type = format!("0x{}::M::T", CORE_CODE_ADDRESS);
+        let functions = abi
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.public, f.returns.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            vec![
+                ("check", false, vec!["bool".to_owned()]),
+                ("new", true, vec![t_type.clone()]),
+            ]
+        );
+        assert_eq!(
+            abi.functions[0].params,
+            vec![
+                ParamAbi {
+                    f_type: "&signer".to_owned(),
+                    is_signer: true,
+                },
+                ParamAbi {
+                    f_type: format!("&{}", t_type),
+                    is_signer: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_public_abi_json() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        ds.publish_module(
+            compiler
+                .compile(
+                    include_str!("../../tests/resources/disassembler/base_1.move"),
+                    Some(CORE_CODE_ADDRESS),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        let source = "
+            module M {
+                use 0x1::Base;
+                resource struct Pool<T: copyable> { t: T, b: vector<Base::Test1> }
+                native public fun create<T: copyable>(s: &signer, t: T): Pool<T>;
+                native public fun value(pool: &mut Pool<u64>): (u64, bool);
+                native fun hidden();
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let abi: serde_json::Value =
+            serde_json::from_str(&public_abi_json(&bytecode).unwrap()).unwrap();
 
-impl Structs {
-    pub fn contains(&self, name: &str) -> bool {
-        self.structs.iter().any(|s| s.name == name)
+        let base = format!("0x{}::Base::Test1", CORE_CODE_ADDRESS);
+        let pool = format!("0x{}::M::Pool", CORE_CODE_ADDRESS);
+        assert_eq!(
+            abi,
+            serde_json::json!({
+                "address": format!("0x{}", CORE_CODE_ADDRESS),
+                "name": "M",
+                "structs": [{
+                    "name": "Pool",
+                    "resource": true,
+                    "native": false,
+                    "type_params": ["copyable"],
+                    "fields": [
+                        { "name": "t", "type": "T1" },
+                        { "name": "b", "type": format!("vector<{}>", base) },
+                    ],
+                }],
+                "functions": [
+                    {
+                        "name": "create",
+                        "native": true,
+                        "type_params": ["copyable"],
+                        "params": [
+                            { "type": "&signer", "is_signer": true },
+                            { "type": "T1", "is_signer": false },
+                        ],
+                        "returns": [format!("{}<T1>", pool)],
+                    },
+                    {
+                        "name": "value",
+                        "native": true,
+                        "type_params": [],
+                        "params": [
+                            { "type": format!("&mut {}<u64>", pool), "is_signer": false },
+                        ],
+                        "returns": ["u64", "bool"],
+                    },
+                ],
+            })
+        );
     }
-}
 
-impl Display for Structs {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for s in &self.structs {
-            writeln!(f, "{}", s)?
-        }
-        Ok(())
+    #[test]
+    pub fn test_public_abi_json_type_param_names() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T1 { f: u64 }
+                struct Pair<A, B> { a: A, b: B }
+                native public fun wrap<A: copyable>(a: A, t: T1): Pair<A, T1>;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let abi: serde_json::Value =
+            serde_json::from_str(&public_abi_json(&bytecode).unwrap()).unwrap();
+
+        let t1 = format!("0x{}::M::T1", CORE_CODE_ADDRESS);
+        let pair = format!("0x{}::M::Pair", CORE_CODE_ADDRESS);
+        assert_eq!(
+            abi["structs"][0]["fields"],
+            serde_json::json!([
+                { "name": "a", "type": "T1" },
+                { "name": "b", "type": "T2" },
+            ])
+        );
+        assert_eq!(
+            abi["functions"][0]["params"],
+            serde_json::json!([
+                { "type": "T1", "is_signer": false },
+                { "type": t1, "is_signer": false },
+            ])
+        );
+        assert_eq!(
+            abi["functions"][0]["returns"],
+            serde_json::json!([format!("{}<T1, {}>", pair, t1)])
+        );
     }
-}
 
-struct FuncResult {
-    ret: Vec<String>,
-}
+    #[test]
+    pub fn test_public_abi_json_snapshot() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T { a: u64 }
+                native public fun new<G: copyable>(a: u64): T;
+                native fun hidden();
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
 
-impl Display for FuncResult {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self.ret.len() {
-            0 => Ok(()),
-            1 => write!(f, ": {}", self.ret[0]),
-            _ => write!(f, ": ({})", self.ret.join(", ")),
-        }
+        let expected = r#"{"address":"0xADDR","name":"M","structs":[{"name":"T","resource":false,"native":false,"type_params":[],"fields":[{"name":"a","type":"u64"}]}],"functions":[{"name":"new","native":true,"type_params":["copyable"],"params":[{"type":"u64","is_signer":false}],"returns":["0xADDR::M::T"]}]}"#;
+        assert_eq!(
+            public_abi_json(&bytecode).unwrap(),
+            expected.replace("ADDR", &CORE_CODE_ADDRESS.to_string())
+        );
     }
-}
 
-struct Acquires {
-    inner: Vec<String>,
-}
+    #[test]
+    pub fn test_reference_rendering() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                resource struct Coin { value: u64 }
+                struct Pool<T> { t: T }
+                native public fun signer_ref(s: &signer): &u64;
+                native public fun coin_ref(c: &mut Coin, v: &vector<u8>): &mut u64;
+                native public fun generic_ref<T>(t: &T, p: &mut Pool<T>): (&T, &mut Pool<u64>);
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let strict = || Config::default().with_strict(true);
+        let signature = module_signature_with_configuration(&bytecode, strict())
+            .unwrap()
+            .to_string();
+        for function in &[
+            "native public fun signer_ref(_arg_1: &signer): &u64;",
+            "native public fun coin_ref(_arg_1: &mut Coin, _arg_2: &vector<u8>): &mut u64;",
+            "native public fun generic_ref<__G_1>(_arg_1: &__G_1, _arg_2: &mut Pool<__G_1>): (&__G_1, &mut Pool<u64>);",
+        ] {
+            assert!(signature.contains(function), "{}", function);
+        }
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        assert_eq!(
+            module_signature(&recompiled).unwrap().to_string(),
+            signature
+        );
 
-impl Display for Acquires {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if !self.inner.is_empty() {
-            write!(f, " acquires {}", self.inner.join(", "))
-        } else {
-            Ok(())
+        // References are not allowed in struct fields.
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        if let StructFieldInformation::Declared(fields) =
+            &mut module.struct_defs[0].field_information
+        {
+            fields[0].signature.0 = SignatureToken::Reference(Box::new(SignatureToken::U64));
         }
+        let mut field_ref = vec![];
+        module.serialize(&mut field_ref).unwrap();
+        assert!(module_signature(&field_ref)
+            .unwrap()
+            .to_string()
+            .contains("        value: &u64,\n"));
+        let err = module_signature_with_configuration(&field_ref, strict())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Field 'value' of the struct 'Coin' has a reference type."
+        );
+
+        // References can't be nested in other types.
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let handle = module.function_defs[2].function.0 as usize;
+        let params = module.function_handles[handle].parameters.0 as usize;
+        module.signatures[params].0[0] = SignatureToken::Vector(Box::new(
+            SignatureToken::Reference(Box::new(SignatureToken::U64)),
+        ));
+        let mut nested_ref = vec![];
+        module.serialize(&mut nested_ref).unwrap();
+        assert!(module_signature(&nested_ref)
+            .unwrap()
+            .to_string()
+            .contains("native public fun signer_ref(_arg_1: vector<&u64>): &u64;"));
+        let err = module_signature_with_configuration(&nested_ref, strict())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Function 'signer_ref' has a nested reference type."
+        );
     }
-}
 
-struct Block {
-    instructions: Vec<Instruction>,
-    indent_size: usize,
-    instructions_indent_size: usize,
-}
+    #[test]
+    pub fn test_identifier_allocator() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T1 { t2: u64 }
+                struct Pool<T> { t: T }
+                native public fun t3(): T1;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let used = module
+            .identifiers
+            .iter()
+            .map(|ident| ident.as_str().to_owned())
+            .collect::<Vec<_>>();
 
-impl Display for Block {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{{")?;
-        for i in &self.instructions {
-            writeln!(
-                f,
-                "{s:width$}{i}",
-                s = "",
-                width = self.instructions_indent_size,
-                i = i
-            )?;
+        let mut allocator = IdentifierAllocator::new(&module);
+        let mut allocated = Vec::new();
+        for prefix in &["T", "t", "__G_"] {
+            for _ in 0..5 {
+                let name = allocator.allocate(prefix);
+                assert!(!used.contains(&name), "{}", name);
+                assert!(!allocated.contains(&name), "{}", name);
+                allocated.push(name);
+            }
         }
-        writeln!(f, "{s:width$}}}", s = "", width = self.indent_size)?;
-        Ok(())
+        assert_eq!(allocated[0], "T2");
+        assert_eq!(allocator.fresh_prefix("__G_"), "__G_");
+        assert_eq!(allocator.fresh_prefix("T"), "T1_");
+
+        // A struct named like a synthesized generic moves the generics to another prefix.
+        let pool = module
+            .identifiers
+            .iter()
+            .position(|ident| ident.as_str() == "T1")
+            .unwrap();
+        module.identifiers[pool] = Identifier::new("__G_1").unwrap();
+        let mut renamed = vec![];
+        module.serialize(&mut renamed).unwrap();
+        let signature = module_signature(&renamed).unwrap().to_string();
+        assert!(signature.contains("    struct Pool<__G_1_1> {\n        t: __G_1_1,\n    }"));
+        assert!(signature.contains("native public fun t3(): __G_1;"));
     }
-}
 
-struct Function {
-    is_public: bool,
-    is_native: bool,
-    name: String,
-    type_params: TypeParams,
-    params: Params,
-    ret: FuncResult,
-    acquires: Acquires,
-    indent_size: usize,
-    body: Block,
-}
+    #[test]
+    pub fn test_validate_disassembly() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct A { f: u64 }
+                struct B { f: bool }
+                native public fun f(): A;
+                native public fun g(): B;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let report = validate_disassembly(&bytecode).unwrap();
+        assert!(report.is_clean(), "{:?}", report);
 
-impl Display for Function {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{s:width$}{native}{p}fun {name}{t_params}({params}){return_}{acquires}{native_end}",
-            s = "",
-            width = self.indent_size,
-            p = if self.is_public { "public " } else { "" },
-            native = if self.is_native { "native " } else { "" },
-            name = self.name,
-            t_params = self.type_params,
-            params = self.params,
-            return_ = self.ret,
-            acquires = self.acquires,
-            native_end = if self.is_native { ";\n" } else { "" },
-        )?;
-        if !self.is_native {
-            write!(f, " {}", self.body)?;
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        if let StructFieldInformation::Declared(fields) =
+            &mut module.struct_defs[1].field_information
+        {
+            fields[0].signature.0 = SignatureToken::Struct(StructHandleIndex(100));
         }
+        let f = module.function_defs[0].function.0 as usize;
+        let g = module.function_defs[1].function.0 as usize;
+        module.function_handles[g].name = module.function_handles[f].name;
+        let mut broken = vec![];
+        module.serialize(&mut broken).unwrap();
 
-        Ok(())
-    }
-}
-
-struct Functions {
-    functions: Vec<Function>,
-}
+        let report = validate_disassembly(&broken).unwrap();
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.issues(),
+            &[
+                "struct 1: struct handle index 100 is out of bounds".to_owned(),
+                "duplicate function name 'f'".to_owned(),
+            ]
+        );
 
-impl Functions {
-    pub fn has_acursors(&self) -> bool {
-        self.functions.iter().any(|f| !f.acquires.inner.is_empty())
+        assert!(validate_disassembly(&[0x1, 0x2]).is_err());
     }
-}
 
-impl Display for Functions {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for s in &self.functions {
-            writeln!(f, "{}", s)?
+    #[test]
+    pub fn test_functions_matching() {
+        let ds = MockDataSource::new();
+        let compiler = Compiler::new(ds.clone());
+        let sources = [
+            "module Coin { struct T { value: u64 } }",
+            "module Event { struct Handle { id: u64 } }",
+        ];
+        for source in &sources {
+            ds.publish_module(compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap())
+                .unwrap();
         }
-        Ok(())
-    }
-}
 
-pub struct ModuleSignature {
-    id: ModuleId,
-    structs: Structs,
-    functions: Functions,
-    imports: Imports,
-}
+        let source = "
+            module M {
+                use 0x1::Coin;
+                use 0x1::Event;
+                struct Account { balance: u64 }
+                native public fun transfer(from: &signer, coin: Coin::T);
+                native public fun transfer_event(): Event::Handle;
+                native fun balance(account: &Account): u64;
+                resource struct Vault { account: Account }
+                public fun vault_balance(addr: address): u64 acquires Vault {
+                    borrow_global<Vault>(addr).account.balance
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let signature = disasm_functions_matching(&bytecode, |name| name == "transfer").unwrap();
+        assert_eq!(
+            signature,
+            format!(
+                "address 0x{address} {{\n\nmodule M {{\n    use 0x{address}::Coin;\n\
+                 \x20   native public fun transfer(_arg_1: &signer, _arg_2: Coin::T);\n\n}}\n}}\n",
+                address = CORE_CODE_ADDRESS
+            )
+        );
 
-impl ModuleSignature {
-    pub fn self_id(&self) -> &ModuleId {
-        &self.id
+        let signature =
+            disasm_functions_matching(&bytecode, |name| name.starts_with("transfer")).unwrap();
+        assert!(signature.contains("fun transfer("));
+        assert!(signature.contains("fun transfer_event("));
+        assert!(signature.contains("use 0x"));
+        assert!(!signature.contains("balance"));
+        assert!(!signature.contains("struct"));
+
+        let signature =
+            disasm_functions_matching(&bytecode, |name| name == "vault_balance").unwrap();
+        assert!(signature.contains("    struct Account {\n        balance: u64,\n    }"));
+        assert!(signature.contains("    resource struct Vault {\n        account: Account,\n    }"));
+        assert!(
+            signature.contains("public fun vault_balance(_arg_1: address): u64 acquires Vault {")
+        );
+        assert!(!signature.contains("use 0x"));
+        assert!(!signature.contains("transfer"));
+        compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
     }
-}
 
-impl Display for ModuleSignature {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(
-            f,
-            "address 0x{address} {{\n\nmodule {name} {{\n{imports}{structs}{functions}}}\n}}",
-            address = self.id.address(),
-            name = self.id.name(),
-            structs = self.structs,
-            functions = self.functions,
-            imports = self.imports,
+    #[test]
+    pub fn test_trailing_comma() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T { a: u64, b: bool }
+                struct E {}
+                native public fun f(a: u64, b: bool): T;
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let trailing = module_signature(&bytecode).unwrap().to_string();
+        assert!(trailing.contains("    struct T {\n        a: u64,\n        b: bool,\n    }"));
+
+        let signature = module_signature_with_configuration(
+            &bytecode,
+            Config::default().with_trailing_comma(false),
         )
+        .unwrap()
+        .to_string();
+        assert!(signature.contains("    struct T {\n        a: u64,\n        b: bool\n    }"));
+        assert!(signature.contains("    struct E {\n    }"));
+        assert!(signature.contains("native public fun f(_arg_1: u64, _arg_2: bool): T;"));
+
+        assert_eq!(
+            compiler
+                .compile(&signature, Some(CORE_CODE_ADDRESS))
+                .unwrap(),
+            compiler
+                .compile(&trailing, Some(CORE_CODE_ADDRESS))
+                .unwrap()
+        );
     }
-}
 
-impl ModuleSignature {}
+    #[test]
+    pub fn test_generic_prefix_collision() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T_1 { f: u64 }
+                struct T_ { f: u64 }
+                struct Pool<T, V> { t: T, v: V, s: T_1 }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let config = Config::new(super::PHANTOM_RESOURCE_NAME, "T_", false);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains(
+            "    struct Pool<T_1_1, T_1_2> {\n        t: T_1_1,\n        v: T_1_2,\n        s: T_1,\n    }"
+        ));
 
-#[cfg(test)]
-mod tests {
-    use libra::libra_types::account_address::AccountAddress;
-    use ds::MockDataSource;
-    use crate::embedded::Compiler;
-    use crate::mv::disassembler::module_signature;
-    use libra::move_core_types::language_storage::CORE_CODE_ADDRESS;
+        let recompiled = compiler
+            .compile(&signature, Some(CORE_CODE_ADDRESS))
+            .unwrap();
+        let config = Config::new(super::PHANTOM_RESOURCE_NAME, "T_", false);
+        assert_eq!(
+            module_signature_with_configuration(&recompiled, config)
+                .unwrap()
+                .to_string(),
+            signature
+        );
+    }
 
     #[test]
-    pub fn test_module_signature() {
-        let ds = MockDataSource::new();
-        let compiler = Compiler::new(ds.clone());
-        ds.publish_module(
+    pub fn test_size_limits() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct A { f: u64 }
+                struct B { f: u64 }
+                native public fun f();
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_max_bytecode_size(bytecode.len());
+        assert!(module_signature_with_configuration(&bytecode, config).is_ok());
+        let config = Config::default().with_max_bytecode_size(bytecode.len() - 1);
+        let err = module_signature_with_configuration(&bytecode, config)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Bytecode size {} exceeds the limit of {}.",
+                bytecode.len(),
+                bytecode.len() - 1
+            )
+        );
+
+        let config = Config::default().with_max_definitions(2, 1);
+        assert!(module_signature_with_configuration(&bytecode, config).is_ok());
+        let config = Config::default().with_max_definitions(1, 1);
+        let err = module_signature_with_configuration(&bytecode, config)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Number of structs 2 exceeds the limit of 1."
+        );
+        let config = Config::default().with_max_definitions(2, 0);
+        let err = module_signature_with_configuration(&bytecode, config)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Number of functions 1 exceeds the limit of 0."
+        );
+    }
+
+    #[test]
+    pub fn test_aligned_fields() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                struct T { a: u64, balance: u128, is_frozen: bool }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config = Config::default().with_aligned_fields(true);
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature.contains(
+            "    struct T {\n        a        : u64,\n        balance  : u128,\n        is_frozen: bool,\n    }"
+        ));
+        assert_eq!(
+            compiler
+                .compile(&signature, Some(CORE_CODE_ADDRESS))
+                .unwrap(),
             compiler
                 .compile(
-                    include_str!("../../tests/resources/disassembler/base.move"),
-                    Some(AccountAddress::new([0x1; 20])),
+                    &module_signature(&bytecode).unwrap().to_string(),
+                    Some(CORE_CODE_ADDRESS)
                 )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_header() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "module M { struct T { f: u64 } }";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+
+        let config =
+            Config::default().with_header("Auto-generated by dvm disassembler.\nDo not edit.");
+        let signature = module_signature_with_configuration(&bytecode, config)
+            .unwrap()
+            .to_string();
+        assert!(signature
+            .starts_with("// Auto-generated by dvm disassembler.\n// Do not edit.\naddress 0x"));
+        assert_eq!(
+            compiler
+                .compile(&signature, Some(CORE_CODE_ADDRESS))
                 .unwrap(),
-        )
-        .unwrap();
-        ds.publish_module(
             compiler
                 .compile(
-                    include_str!("../../tests/resources/disassembler/base_1.move"),
-                    Some(CORE_CODE_ADDRESS),
+                    &module_signature(&bytecode).unwrap().to_string(),
+                    Some(CORE_CODE_ADDRESS)
                 )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_module_constants() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "
+            module M {
+                public fun fee_collector(): address {
+                    0x42
+                }
+
+                public fun prefix(): vector<u8> {
+                    x\"0102ff\"
+                }
+            }
+        ";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let mut constants = module_constants(&bytecode)
+            .unwrap()
+            .into_iter()
+            .map(|(f_type, value)| (f_type.to_string(), value))
+            .collect::<Vec<_>>();
+        constants.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut address = [0; 20];
+        address[19] = 0x42;
+        assert_eq!(
+            constants,
+            vec![
+                (
+                    "address".to_owned(),
+                    ConstValue::Address(AccountAddress::new(address))
+                ),
+                (
+                    "vector<u8>".to_owned(),
+                    ConstValue::Bytes(vec![0x01, 0x02, 0xff])
+                ),
+            ]
+        );
+
+        let source = "module M { struct T { f: u64 } }";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        assert!(module_constants(&bytecode).unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_vector_constant_rendering() {
+        let value = ConstValue::Vector(vec![
+            ConstValue::Vector(vec![ConstValue::U64(1)]),
+            ConstValue::Vector(vec![]),
+            ConstValue::Bytes(vec![0xff]),
+        ]);
+        assert_eq!(value.to_string(), "vector[vector[1u64], vector[], x\"ff\"]");
+    }
+
+    #[test]
+    pub fn test_large_integer_constants() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "module M { struct T { f: u64 } }";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let above_u64 = u128::from(u64::MAX) + 1;
+        module.constant_pool = vec![
+            Constant {
+                type_: SignatureToken::U128,
+                data: lcs::to_bytes(&u128::MAX).unwrap(),
+            },
+            Constant {
+                type_: SignatureToken::U128,
+                data: lcs::to_bytes(&above_u64).unwrap(),
+            },
+            Constant {
+                type_: SignatureToken::U64,
+                data: lcs::to_bytes(&u64::MAX).unwrap(),
+            },
+        ];
+        let mut bytecode = vec![];
+        module.serialize(&mut bytecode).unwrap();
+
+        let constants = module_constants(&bytecode)
+            .unwrap()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constants,
+            vec![
+                ConstValue::U128(u128::MAX),
+                ConstValue::U128(above_u64),
+                ConstValue::U64(u64::MAX),
+            ]
+        );
+
+        let literals = constants
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            literals,
+            vec![
+                "340282366920938463463374607431768211455u128",
+                "18446744073709551616u128",
+                "18446744073709551615u64",
+            ]
+        );
+        assert_eq!(
+            literals[0]
+                .trim_end_matches("u128")
+                .parse::<u128>()
                 .unwrap(),
-        )
-        .unwrap();
+            u128::MAX
+        );
+        assert_eq!(
+            literals[1]
+                .trim_end_matches("u128")
+                .parse::<u128>()
+                .unwrap(),
+            above_u64
+        );
+    }
 
-        for (source, dis) in test_set() {
-            let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
-            let signature = module_signature(&bytecode).unwrap();
-            assert_eq!(&signature.to_string(), dis);
+    #[test]
+    pub fn test_byte_vector_constants() {
+        let compiler = Compiler::new(MockDataSource::new());
+        let source = "module M { struct T { f: u64 } }";
+        let bytecode = compiler.compile(source, Some(CORE_CODE_ADDRESS)).unwrap();
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+        let blobs = vec![
+            b"Hello, Move!\n".to_vec(),
+            vec![0x00, 0xde, 0xad, 0xbe, 0xef, 0xff],
+        ];
+        module.constant_pool = blobs
+            .iter()
+            .map(|blob| Constant {
+                type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+                data: lcs::to_bytes(blob).unwrap(),
+            })
+            .collect();
+        let mut bytecode = vec![];
+        module.serialize(&mut bytecode).unwrap();
 
-            let bytecode = compiler.compile(dis, Some(CORE_CODE_ADDRESS)).unwrap();
-            let signature = module_signature(&bytecode).unwrap();
-            assert_eq!(&signature.to_string(), dis);
-        }
+        let literals = module_constants(&bytecode)
+            .unwrap()
+            .into_iter()
+            .map(|(_, value)| value.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            literals,
+            vec!["x\"48656c6c6f2c204d6f7665210a\"", "x\"00deadbeefff\""]
+        );
     }
 
     fn test_set() -> Vec<(&'static str, &'static str)> {
@@ -728,4 +5827,90 @@ mod tests {
             ),
         ]
     }
+
+    /// Asserts that the bytecode of two modules is equal and reports the first diverged table otherwise.
+    fn assert_modules_eq(left: &[u8], right: &[u8]) {
+        let left = CompiledModule::deserialize(left).unwrap();
+        let right = CompiledModule::deserialize(right).unwrap();
+        if let Some(diff) = module_diff(&left, &right) {
+            panic!("Modules {} diverged. {}", left.self_id(), diff);
+        }
+    }
+
+    /// Returns a description of the first diverged table of two modules.
+    fn module_diff(left: &CompiledModule, right: &CompiledModule) -> Option<String> {
+        let left = left.as_inner();
+        let right = right.as_inner();
+        table_diff(
+            "module_handles",
+            &left.module_handles,
+            &right.module_handles,
+        )
+        .or_else(|| {
+            table_diff(
+                "struct_handles",
+                &left.struct_handles,
+                &right.struct_handles,
+            )
+        })
+        .or_else(|| {
+            table_diff(
+                "function_handles",
+                &left.function_handles,
+                &right.function_handles,
+            )
+        })
+        .or_else(|| table_diff("field_handles", &left.field_handles, &right.field_handles))
+        .or_else(|| {
+            table_diff(
+                "struct_def_instantiations",
+                &left.struct_def_instantiations,
+                &right.struct_def_instantiations,
+            )
+        })
+        .or_else(|| {
+            table_diff(
+                "function_instantiations",
+                &left.function_instantiations,
+                &right.function_instantiations,
+            )
+        })
+        .or_else(|| {
+            table_diff(
+                "field_instantiations",
+                &left.field_instantiations,
+                &right.field_instantiations,
+            )
+        })
+        .or_else(|| table_diff("signatures", &left.signatures, &right.signatures))
+        .or_else(|| table_diff("identifiers", &left.identifiers, &right.identifiers))
+        .or_else(|| {
+            table_diff(
+                "address_identifiers",
+                &left.address_identifiers,
+                &right.address_identifiers,
+            )
+        })
+        .or_else(|| table_diff("constant_pool", &left.constant_pool, &right.constant_pool))
+        .or_else(|| table_diff("struct_defs", &left.struct_defs, &right.struct_defs))
+        .or_else(|| table_diff("function_defs", &left.function_defs, &right.function_defs))
+    }
+
+    fn table_diff<T: PartialEq + Debug>(name: &str, left: &[T], right: &[T]) -> Option<String> {
+        if let Some(index) = left.iter().zip(right).position(|(l, r)| l != r) {
+            Some(format!(
+                "Table '{}' diverged at index {}: {:?} != {:?}",
+                name, index, left[index], right[index]
+            ))
+        } else if left.len() != right.len() {
+            Some(format!(
+                "Table '{}' length diverged: {} != {}",
+                name,
+                left.len(),
+                right.len()
+            ))
+        } else {
+            None
+        }
+    }
 }
@@ -1,13 +1,14 @@
 use libra::libra_types::account_address::AccountAddress;
 use ds::MockDataSource;
 use libra::libra_vm::{
-    file_format::{CompiledScript, CompiledModule},
+    file_format::{CompiledScript, CompiledModule, Bytecode},
     access::ModuleAccess,
 };
 
-use dvm_compiler::Compiler;
+use dvm_compiler::{Compiler, verify_bytecode};
 use anyhow::Error;
 use libra::libra_types::account_config::CORE_CODE_ADDRESS;
+use libra::libra_types::vm_error::VMStatus;
 
 pub fn compile(
     source: &str,
@@ -200,3 +201,24 @@ fn test_build_move() {
         )
         .unwrap();
 }
+
+#[test]
+fn test_compile_and_verify() {
+    let compiler = Compiler::new(MockDataSource::new());
+    let bytecode = compiler
+        .compile_and_verify(
+            "module M { public fun f(): u64 { 1 } }",
+            Some(CORE_CODE_ADDRESS),
+        )
+        .unwrap();
+    verify_bytecode(&bytecode).unwrap();
+
+    // Pop from the empty stack: the module is well-formed but fails the stack check.
+    let mut module = CompiledModule::deserialize(&bytecode).unwrap().into_inner();
+    module.function_defs[0].code.as_mut().unwrap().code = vec![Bytecode::Pop, Bytecode::Ret];
+    let mut broken = vec![];
+    module.serialize(&mut broken).unwrap();
+
+    let err = verify_bytecode(&broken).unwrap_err();
+    assert!(err.downcast_ref::<VMStatus>().is_some());
+}
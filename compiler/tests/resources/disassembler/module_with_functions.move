@@ -32,6 +32,10 @@ module Foo {
               abort 1
      }
 
+     public fun public_with_ret(_x: u64): u64 {
+         abort 1
+     }
+
      fun print_private() {
          abort 1
      }
@@ -65,6 +65,10 @@ module Foo {
 
     native public fun print_resource<__G_1: resource>(_arg_1: &__G_1);
 
+    public fun public_with_ret(_arg_1: u64): u64 {
+        abort 1
+    }
+
     fun return_generic<__G_1, __G_2, __G_3>(): __G_2 {
         abort 1
     }
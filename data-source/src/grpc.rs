@@ -1,11 +1,15 @@
 use std::convert::TryInto;
-use std::sync::Arc;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Error;
-use api::grpc::ds_grpc::{ds_raw_response::ErrorCode, ds_service_client::DsServiceClient, DsAccessPath};
-use crossbeam::channel::{bounded, Receiver, Sender};
+use api::grpc::ds_grpc::{
+    ds_raw_response::ErrorCode, ds_service_client::DsServiceClient, DsAccessPath, DsAccessPaths,
+};
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, Sender};
 use http::Uri;
 use libra_state_view::StateView;
 use libra_types::access_path::AccessPath;
@@ -16,6 +20,7 @@ use tokio::runtime::Runtime;
 use dvm_net::api;
 use dvm_net::prelude::*;
 use dvm_net::tonic;
+use dvm_net::tonic::transport::{Channel, ClientTlsConfig};
 use libra::{libra_state_view, libra_types, move_vm_runtime};
 use libra::libra_vm::errors::VMResult;
 
@@ -24,56 +29,280 @@ use crate::{Clear, DataSource};
 /// Receiver for a channel that handles shutdown signals.
 pub type ShutdownSig = tokio::sync::oneshot::Receiver<()>;
 
+/// Errors of the gRPC data source.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DataSourceError {
+    /// The worker thread serving requests to `dnode` has stopped,
+    /// either after a shutdown signal, a lost connection or a panic.
+    WorkerDied,
+    /// The request was not answered in time.
+    Timeout,
+}
+
+impl fmt::Display for DataSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataSourceError::WorkerDied => write!(f, "Data source worker is not running"),
+            DataSourceError::Timeout => write!(f, "Data source request timed out"),
+        }
+    }
+}
+
+impl std::error::Error for DataSourceError {}
+
+/// Exponential backoff policy of the connection and request retries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_backoff: Duration,
+    /// Maximum number of retries after the first failed attempt.
+    pub max_retries: usize,
+    /// Factor by which the delay grows after each retry.
+    pub multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry with the zero-based number `retry`.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(retry as i32);
+        if backoff < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(backoff)
+        } else {
+            self.max_backoff
+        }
+    }
+}
+
 /// Wrapper around gRPC-based interface to dnode. Used for the resource resolution inside the VM.
 #[derive(Clone)]
 pub struct GrpcDataSource {
     handler: Arc<JoinHandle<()>>,
     sender: Sender<Request>,
+    options: Arc<Options>,
+    /// Connection of the async API, which bypasses the worker.
+    async_client: Arc<Mutex<AsyncClient>>,
+}
+
+/// Lazily created connection of the async API.
+struct AsyncClient {
+    uri: Uri,
+    client: Option<DsServiceClient<Channel>>,
 }
 
 impl GrpcDataSource {
     /// Create an instance of gRPC based data source for VM.
     /// `shutdown_signal` is a oneshot `crossbeam_channel::Sender` to shutdown the service.
     pub fn new(uri: Uri, shutdown_signal: Option<ShutdownSig>) -> Result<GrpcDataSource, Error> {
-        let rt = Runtime::new()?;
-        let (sender, receiver) = bounded(10);
-        let handler =
-            thread::spawn(move || Self::internal_loop(rt, uri, receiver, shutdown_signal));
+        let mut builder = GrpcDataSource::builder(uri);
+        if let Some(shutdown_signal) = shutdown_signal {
+            builder = builder.shutdown_signal(shutdown_signal);
+        }
+        builder.build()
+    }
 
-        Ok(GrpcDataSource {
-            handler: Arc::new(handler),
-            sender,
-        })
+    /// Create a builder of gRPC based data source for VM.
+    pub fn builder(uri: Uri) -> GrpcDataSourceBuilder {
+        GrpcDataSourceBuilder {
+            uri,
+            shutdown_signal: None,
+            options: Default::default(),
+        }
+    }
+
+    /// Reconnect the data source to a new `dnode` endpoint.
+    /// Requests sent before this call are served by the current connection,
+    /// requests sent after it are served by the new one.
+    pub fn set_endpoint(&self, uri: Uri) -> Result<(), Error> {
+        *self.async_client.lock().unwrap() = AsyncClient {
+            uri: uri.clone(),
+            client: None,
+        };
+        self.sender
+            .send(Request::SetEndpoint(uri))
+            .map_err(|_| DataSourceError::WorkerDied.into())
+    }
+
+    /// Fetch the blob stored under the access path without blocking the caller.
+    /// The request is sent from the caller's tokio runtime, bypassing the worker thread.
+    pub async fn get_async(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>, Error> {
+        let mut client = self.async_client().await?;
+        Self::get_raw(&mut client, access_path.clone(), &self.options).await
+    }
+
+    /// Returns the client of the async API, connecting it on the first call.
+    async fn async_client(&self) -> Result<DsServiceClient<Channel>, Error> {
+        let uri = {
+            let async_client = self.async_client.lock().unwrap();
+            if let Some(client) = &async_client.client {
+                return Ok(client.clone());
+            }
+            async_client.uri.clone()
+        };
+
+        let endpoint: Endpoint = uri
+            .clone()
+            .try_into()
+            .map_err(|err| anyhow!("Invalid DS address: {}", err))?;
+        let channel = endpoint
+            .connect_with(&self.options.connect)
+            .await
+            .map_err(|err| anyhow!("Failed to connect to data-source: {}", err))?;
+        let client = DsServiceClient::new(channel);
+
+        let mut async_client = self.async_client.lock().unwrap();
+        // Keep the connection unless the endpoint was changed while connecting.
+        if async_client.uri == uri {
+            async_client.client = Some(client.clone());
+        }
+        Ok(client)
+    }
+
+    /// Check whether a blob is stored under the access path.
+    /// `dnode` has no presence-only request, so the blob is fetched and discarded.
+    pub fn exists(&self, access_path: &AccessPath) -> Result<bool, Error> {
+        StateView::get(self, access_path).map(|blob| blob.is_some())
+    }
+
+    /// Fetch blobs for all access paths.
+    /// Each path succeeds or fails independently of the others.
+    /// Paths which are not resolved before the `deadline` fail with a timeout error.
+    pub fn multi_get_partial(
+        &self,
+        access_paths: &[AccessPath],
+        deadline: Option<Instant>,
+    ) -> Vec<Result<Option<Vec<u8>>, Error>> {
+        // Send all requests before waiting for the responses.
+        let receivers = access_paths
+            .iter()
+            .map(|path| {
+                let (tx, rx) = bounded(1);
+                self.sender
+                    .send(Request::Get {
+                        path: path.clone(),
+                        sender: tx,
+                    })
+                    .map(|_| rx)
+                    .map_err(|_| Error::from(DataSourceError::WorkerDied))
+            })
+            .collect::<Vec<_>>();
+
+        receivers
+            .into_iter()
+            .map(|rx| recv(&rx?, deadline))
+            .collect()
+    }
+
+    /// Deadline of a request sent now.
+    fn deadline(&self) -> Option<Instant> {
+        self.options
+            .request_timeout
+            .map(|timeout| Instant::now() + timeout)
     }
 
     fn internal_loop(
         mut rt: Runtime,
-        ds_addr: Uri,
+        mut ds_addr: Uri,
         receiver: Receiver<Request>,
         mut shutdown_signal: Option<ShutdownSig>,
+        options: Options,
     ) {
+        loop {
+            let client = Self::connect(&mut rt, &ds_addr, &mut shutdown_signal, &options);
+
+            // We are connected if client is Some.
+            if let Some(mut client) = client {
+                info!("Connected to data-source");
+
+                let new_addr = rt.block_on(async {
+                    while !shutdown_signal
+                        .as_mut()
+                        .map(|rx| rx.try_recv().is_ok())
+                        .unwrap_or(false)
+                    {
+                        match receiver.recv() {
+                            Ok(Request::Get { path, sender }) => {
+                                let response = Self::get_raw(&mut client, path, &options).await;
+                                if let Err(err) = sender.send(response) {
+                                    error!("Internal VM-DS channel error: {:?}", err);
+                                }
+                            }
+                            Ok(Request::MultiGet { paths, sender }) => {
+                                let response =
+                                    Self::multi_get_raw(&mut client, paths, &options).await;
+                                if let Err(err) = sender.send(response) {
+                                    error!("Internal VM-DS channel error: {:?}", err);
+                                }
+                            }
+                            Ok(Request::SetEndpoint(uri)) => return Some(uri),
+                            Err(_) => break,
+                        }
+                    }
+                    None
+                });
+
+                if let Some(new_addr) = new_addr {
+                    info!("Switching data-source endpoint to {}", new_addr);
+                    ds_addr = new_addr;
+                } else {
+                    // We there in case of:
+                    // - DS connection is broken,
+                    // - we just received the shutdown signal.
+                    // Anyway, that's the finish. Just log it.
+                    info!("DS client shutted down");
+                    return;
+                }
+            } else {
+                // client is None, so we cannot connect and cannot continue.
+                warn!("Unable to connect to data-source.");
+                return;
+            }
+        }
+    }
+
+    fn connect(
+        rt: &mut Runtime,
+        ds_addr: &Uri,
+        shutdown_signal: &mut Option<ShutdownSig>,
+        options: &Options,
+    ) -> Option<DsServiceClient<Channel>> {
         info!("Connecting to data-source: {}", ds_addr);
-        let client: Option<DsServiceClient<_>> = rt.block_on(async {
-            while !(&mut shutdown_signal)
+        rt.block_on(async {
+            let mut attempts = 0;
+            while !shutdown_signal
                 .as_mut()
                 .map(|rx| rx.try_recv().is_ok())
                 .unwrap_or(false)
             {
                 match ds_addr.clone().try_into() {
                     Err(err) => {
+                        // The address can't become valid by retrying, so the worker stops
+                        // and the pending requests fail with `DataSourceError::WorkerDied`.
                         error!("Invalid DS address: {:?}", err);
-                        std::thread::sleep(Duration::from_millis(500));
-                        std::process::exit(-1);
+                        return None;
                     }
-                    Ok::<Endpoint, _>(endpoint) => match endpoint.connect().await {
-                        Ok(channel) => {
-                            return Some(DsServiceClient::with_interceptor(channel, |req| {
-                                debug!("request DS: {:?}", req);
-                                Ok(req)
-                            }))
+                    Ok::<Endpoint, _>(endpoint) => {
+                        match endpoint.connect_with(&options.connect).await {
+                            Ok(channel) => {
+                                return Some(DsServiceClient::with_interceptor(channel, |req| {
+                                    debug!("request DS: {:?}", req);
+                                    Ok(req)
+                                }))
+                            }
+                            Err(_) => {
+                                attempts += 1;
+                                if options.connect_attempts_exhausted(attempts) {
+                                    error!(
+                                        "Failed to connect to data-source after {} attempts",
+                                        attempts
+                                    );
+                                    return None;
+                                }
+                                tokio::time::delay_for(options.connect_backoff(attempts - 1)).await
+                            }
                         }
-                        Err(_) => tokio::time::delay_for(Duration::from_secs(1)).await,
-                    },
+                    }
                 }
             }
 
@@ -82,76 +311,279 @@ impl GrpcDataSource {
             // So we should log this and return None.
             info!("DS client shutted down");
             None
-        });
+        })
+    }
 
-        // We are connected if client is Some.
-        if let Some(mut client) = client {
-            info!("Connected to data-source");
-
-            rt.block_on(async {
-                while !shutdown_signal
-                    .as_mut()
-                    .map(|rx| rx.try_recv().is_ok())
-                    .unwrap_or(false)
-                {
-                    if let Ok(request) = receiver.recv() {
-                        let grpc_request = tonic::Request::new(access_path_into_ds(request.path));
-                        let res = client.get_raw(grpc_request).await;
-                        if let Err(ref err) = res {
-                            error!(
-                                "Transport-level error received by data source ({:?}). {}",
-                                std::thread::current(),
-                                err
-                            );
-                            std::thread::sleep(Duration::from_millis(500));
-                            std::process::exit(-1);
-                        }
-                        let response = res.unwrap().into_inner();
-                        let error_code = ErrorCode::from_i32(response.error_code)
-                            .expect("Invalid ErrorCode enum value");
-
-                        let response = match error_code {
-                            // if no error code, return blob
-                            ErrorCode::None => Ok(Some(response.blob)),
-                            // if BadRequest, return Err()
-                            ErrorCode::BadRequest => Err(anyhow!(response.error_message)),
-                            // if NoData, return None
-                            ErrorCode::NoData => Ok(None),
-                        };
-                        if let Err(err) = request.sender.send(response) {
-                            error!("Internal VM-DS channel error: {:?}", err);
-                        }
-                    }
+    async fn get_raw(
+        client: &mut DsServiceClient<Channel>,
+        path: AccessPath,
+        options: &Options,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let response = Self::call_with_retries(options, || {
+            let mut client = client.clone();
+            let grpc_request = tonic::Request::new(access_path_into_ds(path.clone()));
+            async move { client.get_raw(grpc_request).await }
+        })
+        .await?;
+        let error_code = ErrorCode::from_i32(response.error_code).ok_or_else(|| {
+            anyhow!(
+                "Data source responded with an unknown error code {}",
+                response.error_code
+            )
+        })?;
+
+        match error_code {
+            // if blob is too large, return Err()
+            ErrorCode::None if options.exceeds_max_blob_size(&response.blob) => Err(anyhow!(
+                "Data source response size {} exceeds the limit of {} bytes",
+                response.blob.len(),
+                options.max_blob_size.unwrap_or_default()
+            )),
+            // if no error code, return blob
+            ErrorCode::None => Ok(Some(response.blob)),
+            // if BadRequest, return Err()
+            ErrorCode::BadRequest => Err(anyhow!(response.error_message)),
+            // if NoData, return None
+            ErrorCode::NoData => Ok(None),
+        }
+    }
+
+    /// Fetch blobs for all access paths in a single request.
+    /// `dnode` responds with an empty blob for a path without data.
+    async fn multi_get_raw(
+        client: &mut DsServiceClient<Channel>,
+        paths: Vec<AccessPath>,
+        options: &Options,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let count = paths.len();
+        let grpc_paths = DsAccessPaths {
+            paths: paths.into_iter().map(access_path_into_ds).collect(),
+        };
+        let response = Self::call_with_retries(options, || {
+            let mut client = client.clone();
+            let grpc_request = tonic::Request::new(grpc_paths.clone());
+            async move { client.multi_get_raw(grpc_request).await }
+        })
+        .await?;
+
+        if response.blobs.len() != count {
+            bail!(
+                "Data source returned {} blobs for {} access paths",
+                response.blobs.len(),
+                count
+            );
+        }
+        response
+            .blobs
+            .into_iter()
+            .map(|blob| {
+                if blob.is_empty() {
+                    Ok(None)
+                } else if options.exceeds_max_blob_size(&blob) {
+                    Err(anyhow!(
+                        "Data source response size {} exceeds the limit of {} bytes",
+                        blob.len(),
+                        options.max_blob_size.unwrap_or_default()
+                    ))
+                } else {
+                    Ok(Some(blob))
                 }
-            });
+            })
+            .collect()
+    }
 
-            // We there in case of:
-            // - DS connection is broken,
-            // - we just received the shutdown signal.
-            // Anyway, that's the finish. Just log it.
-            info!("DS client shutted down");
-        } else {
-            // client is None, so we cannot connect and cannot continue.
-            warn!("Unable to connect to data-source.");
+    /// Perform the call, retrying transport errors according to the retry policy.
+    async fn call_with_retries<T, F, R>(options: &Options, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> R,
+        R: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = match options.request_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, call())
+                    .await
+                    .map_err(|_| DataSourceError::Timeout)?,
+                None => call().await,
+            };
+            let err = match result {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(err) => err,
+            };
+            error!(
+                "Transport-level error received by data source ({:?}). {}",
+                std::thread::current(),
+                err
+            );
+            match &options.retry_policy {
+                Some(policy) if retry < policy.max_retries => {
+                    tokio::time::delay_for(policy.backoff(retry)).await;
+                    retry += 1;
+                }
+                _ => return Err(anyhow!("Data source transport error: {}", err.message())),
+            }
         }
     }
 }
 
+/// Builder of `GrpcDataSource`.
+pub struct GrpcDataSourceBuilder {
+    uri: Uri,
+    shutdown_signal: Option<ShutdownSig>,
+    options: Options,
+}
+
+impl GrpcDataSourceBuilder {
+    /// Set a oneshot receiver to shutdown the service.
+    pub fn shutdown_signal(mut self, shutdown_signal: ShutdownSig) -> Self {
+        self.shutdown_signal = Some(shutdown_signal);
+        self
+    }
+
+    /// Set the TCP keepalive interval of the connection to `dnode`.
+    /// `None` disables keepalive.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.options.connect.tcp_keepalive = interval;
+        self
+    }
+
+    /// Set the initial HTTP/2 stream and connection window sizes in bytes.
+    /// `None` keeps the transport default.
+    pub fn initial_window_sizes(mut self, stream: Option<u32>, connection: Option<u32>) -> Self {
+        self.options.connect.initial_stream_window_size = stream;
+        self.options.connect.initial_connection_window_size = connection;
+        self
+    }
+
+    /// Set the TLS configuration of the connection to `dnode`: CA certificate and client identity.
+    /// `https` endpoints without a configuration trust the system roots.
+    pub fn tls_config(mut self, tls_config: ClientTlsConfig) -> Self {
+        self.options.connect.tls = Some(tls_config);
+        self
+    }
+
+    /// Set the maximum number of attempts to connect to `dnode`.
+    /// After the last failed attempt the worker stops and requests fail with `DataSourceError::WorkerDied`.
+    /// Attempts are unlimited by default.
+    pub fn max_connect_attempts(mut self, max_connect_attempts: usize) -> Self {
+        self.options.max_connect_attempts = Some(max_connect_attempts);
+        self
+    }
+
+    /// Set the backoff policy of the connection and request retries.
+    /// The connection is retried every second without a limit and
+    /// failed requests are not retried by default.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.options.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Set the timeout of a request to `dnode`.
+    /// Requests which are not answered in time fail with `DataSourceError::Timeout`.
+    /// Requests wait without a limit by default.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.options.request_timeout = Some(request_timeout);
+        self
+    }
+
+    /// Set the maximum size of a blob received from `dnode`.
+    /// Larger responses are returned as errors.
+    pub fn max_blob_size(mut self, max_blob_size: usize) -> Self {
+        self.options.max_blob_size = Some(max_blob_size);
+        self
+    }
+
+    /// Create an instance of gRPC based data source for VM.
+    pub fn build(self) -> Result<GrpcDataSource, Error> {
+        let GrpcDataSourceBuilder {
+            uri,
+            shutdown_signal,
+            options,
+        } = self;
+
+        let async_client = AsyncClient {
+            uri: uri.clone(),
+            client: None,
+        };
+        let worker_options = options.clone();
+        let rt = Runtime::new()?;
+        let (sender, receiver) = bounded(10);
+        let handler = thread::spawn(move || {
+            GrpcDataSource::internal_loop(rt, uri, receiver, shutdown_signal, worker_options)
+        });
+
+        Ok(GrpcDataSource {
+            handler: Arc::new(handler),
+            sender,
+            options: Arc::new(options),
+            async_client: Arc::new(Mutex::new(async_client)),
+        })
+    }
+}
+
+/// Data source worker options.
+#[derive(Default, Clone)]
+struct Options {
+    /// Maximum size of a blob received from `dnode`.
+    max_blob_size: Option<usize>,
+    /// Transport options of the connection to `dnode`.
+    connect: ConnectOptions,
+    /// Maximum number of attempts to connect to `dnode`.
+    max_connect_attempts: Option<usize>,
+    /// Backoff policy of the connection and request retries.
+    retry_policy: Option<RetryPolicy>,
+    /// Timeout of a request to `dnode`.
+    request_timeout: Option<Duration>,
+}
+
+impl Options {
+    fn exceeds_max_blob_size(&self, blob: &[u8]) -> bool {
+        self.max_blob_size
+            .map(|max_blob_size| blob.len() > max_blob_size)
+            .unwrap_or(false)
+    }
+
+    fn connect_attempts_exhausted(&self, attempts: usize) -> bool {
+        let policy_attempts = self
+            .retry_policy
+            .as_ref()
+            .map(|policy| policy.max_retries + 1);
+        self.max_connect_attempts
+            .into_iter()
+            .chain(policy_attempts)
+            .any(|max_attempts| attempts >= max_attempts)
+    }
+
+    fn connect_backoff(&self, retry: usize) -> Duration {
+        self.retry_policy
+            .as_ref()
+            .map(|policy| policy.backoff(retry))
+            .unwrap_or_else(|| Duration::from_secs(1))
+    }
+}
+
 impl StateView for GrpcDataSource {
     fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>, Error> {
         let (tx, rx) = bounded(0);
-        self.sender.send(Request {
-            path: access_path.clone(),
-            sender: tx,
-        })?;
-        rx.recv()?
+        self.sender
+            .send(Request::Get {
+                path: access_path.clone(),
+                sender: tx,
+            })
+            .map_err(|_| DataSourceError::WorkerDied)?;
+        // The reply sender is dropped without an answer if the worker stops or panics.
+        recv(&rx, self.deadline())
     }
 
     fn multi_get(&self, access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>, Error> {
-        access_paths
-            .iter()
-            .map(|path| StateView::get(self, path))
-            .collect()
+        let (tx, rx) = bounded(0);
+        self.sender
+            .send(Request::MultiGet {
+                paths: access_paths.to_vec(),
+                sender: tx,
+            })
+            .map_err(|_| DataSourceError::WorkerDied)?;
+        recv(&rx, self.deadline())
     }
 
     fn is_genesis(&self) -> bool {
@@ -159,14 +591,38 @@ impl StateView for GrpcDataSource {
     }
 }
 
+/// Wait for the worker reply until the `deadline`.
+fn recv<T>(rx: &Receiver<Result<T, Error>>, deadline: Option<Instant>) -> Result<T, Error> {
+    match deadline {
+        Some(deadline) => rx
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|err| match err {
+                RecvTimeoutError::Timeout => DataSourceError::Timeout,
+                RecvTimeoutError::Disconnected => DataSourceError::WorkerDied,
+            })?,
+        None => rx.recv().map_err(|_| DataSourceError::WorkerDied)?,
+    }
+}
+
 /// Convert Libra's `AccessPath` into gRPC `DsAccessPath`.
 pub fn access_path_into_ds(ap: AccessPath) -> DsAccessPath {
     DsAccessPath::new(ap.address.to_vec(), ap.path)
 }
 
-struct Request {
-    path: AccessPath,
-    sender: Sender<Result<Option<Vec<u8>>, Error>>,
+/// Message to the data source worker.
+enum Request {
+    /// Fetch the blob stored under the access path.
+    Get {
+        path: AccessPath,
+        sender: Sender<Result<Option<Vec<u8>>, Error>>,
+    },
+    /// Fetch the blobs stored under the access paths in a single request.
+    MultiGet {
+        paths: Vec<AccessPath>,
+        sender: Sender<Result<Vec<Option<Vec<u8>>>, Error>>,
+    },
+    /// Reconnect to the new endpoint.
+    SetEndpoint(Uri),
 }
 
 impl RemoteCache for GrpcDataSource {
@@ -5,6 +5,7 @@ use lru::LruCache;
 
 use libra::libra_state_view::StateView;
 use libra::libra_types::access_path::AccessPath;
+use libra::libra_types::vm_error::{StatusCode, VMStatus};
 use libra::libra_vm::errors::VMResult;
 use libra::move_vm_runtime::data_cache::RemoteCache;
 
@@ -92,7 +93,8 @@ where
     D: DataSource,
 {
     fn get(&self, access_path: &AccessPath) -> VMResult<Option<Vec<u8>>> {
-        RemoteCache::get(&self.inner, access_path)
+        // Go through the cache, the VM resolves modules via `RemoteCache`.
+        StateView::get(self, access_path).map_err(|_| VMStatus::new(StatusCode::STORAGE_ERROR))
     }
 }
 
@@ -5,9 +5,14 @@ use dvm_net::tonic::{Request, Response, Status};
 use dvm_net::api::grpc;
 use grpc::ds_grpc::ds_service_server::{DsServiceServer, DsService};
 use grpc::ds_grpc::{DsAccessPath, DsRawResponse, DsAccessPaths, DsRawResponses};
-use std::time::Duration;
-use dvm_data_source::GrpcDataSource;
+use std::time::{Duration, Instant};
+use grpc::ds_grpc::ds_raw_response::ErrorCode;
+use dvm_data_source::{Clear, GrpcDataSource, ModuleCache};
+use dvm_data_source::grpc::{DataSourceError, RetryPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use libra::libra_state_view::StateView;
+use libra::move_vm_runtime::data_cache::RemoteCache;
 use libra::libra_types::{access_path::AccessPath, account_address::AccountAddress};
 
 const ADDRESS: &str = "127.0.0.1:8080";
@@ -36,13 +41,177 @@ impl DsService for DataSourceService {
     }
 }
 
-pub fn run_ds_service_mock() {
+/// Data source which responds with the `size` marker bytes to every request.
+struct MarkerService {
+    marker: u8,
+    size: usize,
+}
+
+impl MarkerService {
+    fn new(marker: u8) -> MarkerService {
+        MarkerService { marker, size: 1 }
+    }
+}
+
+#[tonic::async_trait]
+impl DsService for MarkerService {
+    async fn get_raw(
+        &self,
+        _request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        Ok(Response::new(DsRawResponse::with_blob(&vec![
+            self.marker;
+            self.size
+        ])))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        _request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        Err(Status::invalid_argument("method not implemented."))
+    }
+}
+
+/// Data source which responds according to the first byte of the path:
+/// `0` - blob, `1` - no data, `255` - unknown error code, otherwise - bad request.
+struct PartialService;
+
+#[tonic::async_trait]
+impl DsService for PartialService {
+    async fn get_raw(
+        &self,
+        request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        let request: DsAccessPath = request.into_inner();
+        let (blob, error_code, error_message) = match request.path[0] {
+            0 => (request.path, ErrorCode::None as i32, ""),
+            1 => (vec![], ErrorCode::NoData as i32, ""),
+            255 => (vec![], 255, ""),
+            _ => (vec![], ErrorCode::BadRequest as i32, "bad path"),
+        };
+        Ok(Response::new(DsRawResponse {
+            blob,
+            error_code,
+            error_message: error_message.to_owned(),
+        }))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        _request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        Err(Status::invalid_argument("method not implemented."))
+    }
+}
+
+/// Data source which fails every request at the transport level.
+#[derive(Default)]
+struct UnavailableService {
+    requests: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl DsService for UnavailableService {
+    async fn get_raw(
+        &self,
+        _request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        Err(Status::unavailable("connection dropped"))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        _request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        Err(Status::unavailable("connection dropped"))
+    }
+}
+
+/// Data source which counts the requests and responds with the path to each of them.
+#[derive(Default)]
+struct CountingService {
+    requests: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl DsService for CountingService {
+    async fn get_raw(
+        &self,
+        request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        Ok(Response::new(DsRawResponse::with_blob(
+            &request.into_inner().path,
+        )))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        _request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        Err(Status::invalid_argument("method not implemented."))
+    }
+}
+
+/// Data source which answers after the `delay`.
+struct SlowService {
+    delay: Duration,
+}
+
+#[tonic::async_trait]
+impl DsService for SlowService {
+    async fn get_raw(
+        &self,
+        _request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        tokio::time::delay_for(self.delay).await;
+        Ok(Response::new(DsRawResponse::with_blob(&vec![1])))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        _request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        Err(Status::invalid_argument("method not implemented."))
+    }
+}
+
+/// Data source which serves only batched requests.
+/// Paths starting with `0` have data, other paths have none.
+struct BatchService;
+
+#[tonic::async_trait]
+impl DsService for BatchService {
+    async fn get_raw(
+        &self,
+        _request: Request<DsAccessPath>,
+    ) -> Result<Response<DsRawResponse>, Status> {
+        Err(Status::invalid_argument("method not implemented."))
+    }
+
+    async fn multi_get_raw(
+        &self,
+        request: Request<DsAccessPaths>,
+    ) -> Result<Response<DsRawResponses>, Status> {
+        let blobs = request
+            .into_inner()
+            .paths
+            .into_iter()
+            .map(|path| if path.path[0] == 0 { path.path } else { vec![] })
+            .collect();
+        Ok(Response::new(DsRawResponses { blobs }))
+    }
+}
+
+pub fn run_service<S: DsService>(address: &'static str, service: S) {
     thread::spawn(move || {
         let mut rt = Runtime::new().unwrap();
         rt.block_on(async {
             Server::builder()
-                .add_service(DsServiceServer::new(DataSourceService {}))
-                .serve(ADDRESS.parse().unwrap())
+                .add_service(DsServiceServer::new(service))
+                .serve(address.parse().unwrap())
                 .await
                 .unwrap();
         });
@@ -50,6 +219,10 @@ pub fn run_ds_service_mock() {
     thread::sleep(Duration::from_secs(1));
 }
 
+pub fn run_ds_service_mock() {
+    run_service(ADDRESS, DataSourceService {});
+}
+
 #[test]
 fn test_grpc_ds() {
     run_ds_service_mock();
@@ -90,3 +263,276 @@ fn test_grpc_ds() {
 
     assert!(handlers.into_iter().map(|h| h.join().unwrap()).all(|v| v));
 }
+
+#[test]
+fn test_set_endpoint() {
+    run_service("127.0.0.1:8081", MarkerService::new(1));
+    run_service("127.0.0.1:8082", MarkerService::new(2));
+    let ds = GrpcDataSource::new("http://127.0.0.1:8081".parse().unwrap(), None).unwrap();
+
+    let path = AccessPath::new(AccountAddress::random(), vec![]);
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![1]));
+
+    ds.set_endpoint("http://127.0.0.1:8082".parse().unwrap())
+        .unwrap();
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![2]));
+}
+
+#[test]
+fn test_max_blob_size() {
+    run_service(
+        "127.0.0.1:8083",
+        MarkerService {
+            marker: 1,
+            size: 32,
+        },
+    );
+    let path = AccessPath::new(AccountAddress::random(), vec![]);
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8083".parse().unwrap())
+        .max_blob_size(32)
+        .build()
+        .unwrap();
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![1; 32]));
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8083".parse().unwrap())
+        .max_blob_size(16)
+        .build()
+        .unwrap();
+    let err = ds.get(&path).unwrap_err();
+    assert!(err.to_string().contains("exceeds the limit of 16 bytes"));
+}
+
+#[test]
+fn test_multi_get_partial() {
+    run_service("127.0.0.1:8084", PartialService);
+    let ds = GrpcDataSource::new("http://127.0.0.1:8084".parse().unwrap(), None).unwrap();
+
+    let paths = [0, 1, 2, 0]
+        .iter()
+        .map(|tag| AccessPath::new(AccountAddress::random(), vec![*tag, 42]))
+        .collect::<Vec<_>>();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let response = ds.multi_get_partial(&paths, Some(deadline));
+
+    assert_eq!(response.len(), 4);
+    assert_eq!(response[0].as_ref().unwrap(), &Some(vec![0, 42]));
+    assert_eq!(response[1].as_ref().unwrap(), &None);
+    assert_eq!(response[2].as_ref().unwrap_err().to_string(), "bad path");
+    assert_eq!(response[3].as_ref().unwrap(), &Some(vec![0, 42]));
+}
+
+#[test]
+fn test_worker_died() {
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    shutdown_tx.send(()).unwrap();
+    // Nothing listens on the port, so the worker stops on the shutdown signal while connecting.
+    let ds =
+        GrpcDataSource::new("http://127.0.0.1:8085".parse().unwrap(), Some(shutdown_rx)).unwrap();
+
+    let path = AccessPath::new(AccountAddress::random(), vec![0]);
+    for _ in 0..2 {
+        let err = ds.get(&path).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DataSourceError>(),
+            Some(&DataSourceError::WorkerDied)
+        );
+        assert_eq!(err.to_string(), "Data source worker is not running");
+    }
+}
+
+#[test]
+fn test_connect_options() {
+    let size = 256 * 1024;
+    run_service("127.0.0.1:8086", MarkerService { marker: 7, size });
+    let path = AccessPath::new(AccountAddress::random(), vec![]);
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8086".parse().unwrap())
+        .tcp_keepalive(Some(Duration::from_secs(1)))
+        .initial_window_sizes(Some(65_535), Some(65_535))
+        .build()
+        .unwrap();
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![7; size]));
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8086".parse().unwrap())
+        .tcp_keepalive(None)
+        .initial_window_sizes(None, None)
+        .build()
+        .unwrap();
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![7; size]));
+}
+
+#[test]
+fn test_exists() {
+    run_service("127.0.0.1:8087", PartialService);
+    let ds = GrpcDataSource::new("http://127.0.0.1:8087".parse().unwrap(), None).unwrap();
+    let path = |tag: u8| AccessPath::new(AccountAddress::random(), vec![tag]);
+
+    assert!(ds.exists(&path(0)).unwrap());
+    assert!(!ds.exists(&path(1)).unwrap());
+    assert_eq!(ds.exists(&path(2)).unwrap_err().to_string(), "bad path");
+}
+
+#[test]
+fn test_max_connect_attempts() {
+    // Nothing listens on the port, so the worker gives up after the first attempt.
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8088".parse().unwrap())
+        .max_connect_attempts(1)
+        .build()
+        .unwrap();
+
+    let path = AccessPath::new(AccountAddress::random(), vec![0]);
+    let err = ds.get(&path).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<DataSourceError>(),
+        Some(&DataSourceError::WorkerDied)
+    );
+}
+
+#[test]
+fn test_transport_error() {
+    run_service("127.0.0.1:8089", UnavailableService::default());
+    let ds = GrpcDataSource::new("http://127.0.0.1:8089".parse().unwrap(), None).unwrap();
+
+    // The worker keeps serving requests after a transport error.
+    let path = AccessPath::new(AccountAddress::random(), vec![0]);
+    for _ in 0..2 {
+        let err = ds.get(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Data source transport error: connection dropped"
+        );
+    }
+}
+
+#[test]
+fn test_retry_policy() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(300),
+        max_retries: 3,
+        multiplier: 2.0,
+    };
+    let backoff = (0..policy.max_retries)
+        .map(|retry| policy.backoff(retry))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        backoff,
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            Duration::from_millis(300)
+        ]
+    );
+
+    let service = UnavailableService::default();
+    let requests = service.requests.clone();
+    run_service("127.0.0.1:8090", service);
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8090".parse().unwrap())
+        .retry_policy(policy)
+        .build()
+        .unwrap();
+
+    let path = AccessPath::new(AccountAddress::random(), vec![0]);
+    let start = Instant::now();
+    let err = ds.get(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Data source transport error: connection dropped"
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+    assert!(start.elapsed() >= Duration::from_millis(600));
+}
+
+#[test]
+fn test_multi_get() {
+    run_service("127.0.0.1:8091", BatchService);
+    let ds = GrpcDataSource::new("http://127.0.0.1:8091".parse().unwrap(), None).unwrap();
+
+    let paths = [0, 1, 0, 1]
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| AccessPath::new(AccountAddress::random(), vec![*tag, i as u8]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ds.multi_get(&paths).unwrap(),
+        vec![Some(vec![0, 0]), None, Some(vec![0, 2]), None]
+    );
+    assert!(ds.multi_get(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_module_cache() {
+    let service = CountingService::default();
+    let requests = service.requests.clone();
+    run_service("127.0.0.1:8092", service);
+    let ds = GrpcDataSource::new("http://127.0.0.1:8092".parse().unwrap(), None).unwrap();
+    let ds = ModuleCache::new(ds, 16);
+
+    // Modules are cached, resources are always fetched.
+    let module = AccessPath::new(AccountAddress::random(), vec![0, 1]);
+    let resource = AccessPath::new(AccountAddress::random(), vec![1, 1]);
+    for _ in 0..2 {
+        assert_eq!(StateView::get(&ds, &module).unwrap(), Some(vec![0, 1]));
+        assert_eq!(RemoteCache::get(&ds, &module).unwrap(), Some(vec![0, 1]));
+        assert_eq!(RemoteCache::get(&ds, &resource).unwrap(), Some(vec![1, 1]));
+    }
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    ds.clear();
+    assert_eq!(RemoteCache::get(&ds, &module).unwrap(), Some(vec![0, 1]));
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn test_request_timeout() {
+    let delay = Duration::from_millis(500);
+    run_service("127.0.0.1:8093", SlowService { delay });
+    let path = AccessPath::new(AccountAddress::random(), vec![0]);
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8093".parse().unwrap())
+        .request_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    let err = ds.get(&path).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<DataSourceError>(),
+        Some(&DataSourceError::Timeout)
+    );
+    assert!(start.elapsed() < delay);
+
+    let ds = GrpcDataSource::builder("http://127.0.0.1:8093".parse().unwrap())
+        .request_timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+    assert_eq!(ds.get(&path).unwrap(), Some(vec![1]));
+}
+
+#[test]
+fn test_get_async() {
+    run_service("127.0.0.1:8094", PartialService);
+    let ds = GrpcDataSource::new("http://127.0.0.1:8094".parse().unwrap(), None).unwrap();
+    let path = |tag: u8| AccessPath::new(AccountAddress::random(), vec![tag]);
+
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        assert_eq!(ds.get_async(&path(0)).await.unwrap(), Some(vec![0]));
+        assert_eq!(ds.get_async(&path(1)).await.unwrap(), None);
+        let err = ds.get_async(&path(2)).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad path");
+        let err = ds.get_async(&path(255)).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Data source responded with an unknown error code 255"
+        );
+    });
+
+    // The worker keeps serving requests after an unknown error code.
+    let err = ds.get(&path(255)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Data source responded with an unknown error code 255"
+    );
+    assert_eq!(ds.get(&path(0)).unwrap(), Some(vec![0]));
+}
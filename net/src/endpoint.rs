@@ -5,9 +5,10 @@ use std::{
     path::Path,
 };
 use std::io::{Error as IoError, ErrorKind};
+use std::time::Duration;
 use http::Uri;
 use crate::tonic;
-use tonic::transport::Channel;
+use tonic::transport::{Channel, ClientTlsConfig};
 
 #[derive(Debug, Clone)]
 pub enum Endpoint {
@@ -17,9 +18,17 @@ pub enum Endpoint {
 
 impl Endpoint {
     pub async fn connect(self) -> Result<Channel, Box<dyn std::error::Error>> {
+        self.connect_with(&ConnectOptions::default()).await
+    }
+
+    /// Connect with the transport tuned by `options`.
+    pub async fn connect_with(
+        self,
+        options: &ConnectOptions,
+    ) -> Result<Channel, Box<dyn std::error::Error>> {
         match self {
-            Endpoint::Http(inner) => futures::future::Either::Left(inner.connect()),
-            Endpoint::Ipc(inner) => futures::future::Either::Right(inner.connect()),
+            Endpoint::Http(inner) => futures::future::Either::Left(inner.connect_with(options)),
+            Endpoint::Ipc(inner) => futures::future::Either::Right(inner.connect_with(options)),
         }
         .await
     }
@@ -41,6 +50,80 @@ impl Endpoint {
     }
 }
 
+/// Transport options applied to the connection before connecting.
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    /// TCP keepalive interval.
+    /// Keeps idle connections from being dropped by intermediaries. Not used by IPC.
+    pub tcp_keepalive: Option<Duration>,
+    /// Initial HTTP/2 stream-level flow control window size in bytes.
+    pub initial_stream_window_size: Option<u32>,
+    /// Initial HTTP/2 connection-level flow control window size in bytes.
+    pub initial_connection_window_size: Option<u32>,
+    /// TLS configuration: CA certificate, client identity and domain name.
+    /// `https` connections without a configuration trust the system roots. Not used by IPC.
+    pub tls: Option<ClientTlsConfig>,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        ConnectOptions {
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            initial_stream_window_size: Some(1024 * 1024),
+            initial_connection_window_size: Some(2 * 1024 * 1024),
+            tls: None,
+        }
+    }
+}
+
+impl ConnectOptions {
+    /// Applies the transport options.
+    fn apply<E: EndpointSettings>(&self, endpoint: E) -> E {
+        endpoint
+            .tcp_keepalive(self.tcp_keepalive)
+            .initial_stream_window_size(self.initial_stream_window_size)
+            .initial_connection_window_size(self.initial_connection_window_size)
+    }
+
+    /// Applies the TLS configuration.
+    /// `https` endpoints without a configuration trust the system roots.
+    fn apply_tls<E: EndpointSettings>(&self, endpoint: E, scheme: Option<&str>) -> E {
+        match (&self.tls, scheme) {
+            (Some(tls), _) => endpoint.tls_config(tls.clone()),
+            (None, Some("https")) => endpoint.tls_config(ClientTlsConfig::new()),
+            (None, _) => endpoint,
+        }
+    }
+}
+
+/// Endpoint settings tuned by `ConnectOptions`.
+/// Implemented by the tonic endpoint, which doesn't expose the configured values,
+/// so tests can observe the applied options through another implementation.
+trait EndpointSettings: Sized {
+    fn tcp_keepalive(self, interval: Option<Duration>) -> Self;
+    fn initial_stream_window_size(self, size: Option<u32>) -> Self;
+    fn initial_connection_window_size(self, size: Option<u32>) -> Self;
+    fn tls_config(self, tls: ClientTlsConfig) -> Self;
+}
+
+impl EndpointSettings for tonic::transport::Endpoint {
+    fn tcp_keepalive(self, interval: Option<Duration>) -> Self {
+        tonic::transport::Endpoint::tcp_keepalive(self, interval)
+    }
+
+    fn initial_stream_window_size(self, size: Option<u32>) -> Self {
+        tonic::transport::Endpoint::initial_stream_window_size(self, size)
+    }
+
+    fn initial_connection_window_size(self, size: Option<u32>) -> Self {
+        tonic::transport::Endpoint::initial_connection_window_size(self, size)
+    }
+
+    fn tls_config(self, tls: ClientTlsConfig) -> Self {
+        tonic::transport::Endpoint::tls_config(self, tls)
+    }
+}
+
 /// Create Endpoint with inner URI.
 /// Supports http and https.
 /// Can `TryInto` into `std::net::SocketAddr`
@@ -58,14 +141,29 @@ pub struct Ipc(pub(crate) std::path::PathBuf);
 impl Http {
     /// Connect using default transport (http2).
     pub async fn connect(self) -> Result<Channel, Box<dyn std::error::Error>> {
-        trace!("connecting tcp/ip {:?}", &self.0);
-        let conn = tonic::transport::Endpoint::new(self.0.to_string())?
-            .connect()
-            .await?;
+        self.connect_with(&ConnectOptions::default()).await
+    }
+
+    /// Connect using default transport (http2) tuned by `options`.
+    pub async fn connect_with(
+        self,
+        options: &ConnectOptions,
+    ) -> Result<Channel, Box<dyn std::error::Error>> {
+        trace!("connecting tcp/ip {:?} with {:?}", &self.0, options);
+        let conn = self.endpoint(options)?.connect().await?;
         trace!("connected tcp/ip {:?}", &self.0);
         Ok(conn)
     }
 
+    /// Create the transport endpoint tuned by `options`.
+    fn endpoint(
+        &self,
+        options: &ConnectOptions,
+    ) -> Result<tonic::transport::Endpoint, Box<dyn std::error::Error>> {
+        let endpoint = options.apply(tonic::transport::Endpoint::new(self.0.to_string())?);
+        Ok(options.apply_tls(endpoint, self.0.scheme_str()))
+    }
+
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         self.0.to_string()
@@ -82,13 +180,22 @@ impl Ipc {
 
     /// Connect using UDS transport.
     pub async fn connect(self) -> Result<Channel, Box<dyn std::error::Error>> {
+        self.connect_with(&ConnectOptions::default()).await
+    }
+
+    /// Connect using UDS transport tuned by `options`.
+    pub async fn connect_with(
+        self,
+        options: &ConnectOptions,
+    ) -> Result<Channel, Box<dyn std::error::Error>> {
         use tonic::transport::Endpoint;
         use crate::transport::Stream;
         use tower::service_fn;
 
         trace!("connecting ipc {:?}", &self.0);
         // Here magic: we need to trigger fallback, so passing valid uri but unsupported
-        let channel = Endpoint::try_from("ipc://dummy")?
+        let channel = options
+            .apply(Endpoint::try_from("ipc://dummy")?)
             .connect_with_connector(service_fn(move |_: Uri| Stream::connect(self.0.clone())))
             .await?;
         trace!("connected ipc");
@@ -261,6 +368,7 @@ impl std::fmt::Display for Endpoint {
 mod tests {
     use super::*;
     use std::path::PathBuf;
+    use tonic::transport::Certificate;
 
     // test URIs & paths:
     const IPC_ABS: &str = "ipc://tmp/dir/file";
@@ -363,6 +471,85 @@ mod tests {
         }
     }
 
+    #[test]
+    fn default_connect_options() {
+        let options = ConnectOptions::default();
+        assert_eq!(options.tcp_keepalive, Some(Duration::from_secs(60)));
+        assert_eq!(options.initial_stream_window_size, Some(1024 * 1024));
+        assert_eq!(
+            options.initial_connection_window_size,
+            Some(2 * 1024 * 1024)
+        );
+        assert!(options.tls.is_none());
+    }
+
+    /// Endpoint settings which record the applied values.
+    #[derive(Default)]
+    struct RecordedSettings {
+        tcp_keepalive: Option<Option<Duration>>,
+        initial_stream_window_size: Option<Option<u32>>,
+        initial_connection_window_size: Option<Option<u32>>,
+        tls: bool,
+    }
+
+    impl EndpointSettings for RecordedSettings {
+        fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+            self.tcp_keepalive = Some(interval);
+            self
+        }
+
+        fn initial_stream_window_size(mut self, size: Option<u32>) -> Self {
+            self.initial_stream_window_size = Some(size);
+            self
+        }
+
+        fn initial_connection_window_size(mut self, size: Option<u32>) -> Self {
+            self.initial_connection_window_size = Some(size);
+            self
+        }
+
+        fn tls_config(mut self, _tls: ClientTlsConfig) -> Self {
+            self.tls = true;
+            self
+        }
+    }
+
+    #[test]
+    fn apply_connect_options() {
+        let options = ConnectOptions {
+            tcp_keepalive: Some(Duration::from_secs(5)),
+            initial_stream_window_size: Some(65_535),
+            initial_connection_window_size: None,
+            tls: None,
+        };
+        let settings = options.apply(RecordedSettings::default());
+        assert_eq!(settings.tcp_keepalive, Some(Some(Duration::from_secs(5))));
+        assert_eq!(settings.initial_stream_window_size, Some(Some(65_535)));
+        assert_eq!(settings.initial_connection_window_size, Some(None));
+        assert!(!settings.tls);
+
+        assert!(
+            !options
+                .apply_tls(RecordedSettings::default(), Some("http"))
+                .tls
+        );
+        assert!(
+            options
+                .apply_tls(RecordedSettings::default(), Some("https"))
+                .tls
+        );
+
+        let options = ConnectOptions {
+            tls: Some(ClientTlsConfig::new()),
+            ..Default::default()
+        };
+        assert!(
+            options
+                .apply_tls(RecordedSettings::default(), Some("http"))
+                .tls
+        );
+    }
+
     #[test]
     fn to_soc_http() {
         for (i, uri) in HTTP_URI[..1].iter().enumerate() {
@@ -373,4 +560,22 @@ mod tests {
             assert_eq!(HTTP_SOC[i], &soc);
         }
     }
+
+    #[test]
+    fn http_endpoint_with_tls() {
+        let ca = Certificate::from_pem(include_str!("../tests/resources/ca.pem"));
+        let options = ConnectOptions {
+            tls: Some(
+                ClientTlsConfig::new()
+                    .ca_certificate(ca)
+                    .domain_name("localhost"),
+            ),
+            ..Default::default()
+        };
+        let http = Http("https://localhost:50042".parse().unwrap());
+        assert!(http.endpoint(&options).is_ok());
+
+        // `https` without a configuration trusts the system roots.
+        assert!(http.endpoint(&ConnectOptions::default()).is_ok());
+    }
 }
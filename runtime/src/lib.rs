@@ -10,3 +10,6 @@ pub mod gas_schedule;
 
 /// Defines structures for script execution inside VM.
 pub mod move_vm;
+
+/// Helpers for reading resources and presenting their values.
+pub mod resources;
@@ -0,0 +1,280 @@
+use std::str;
+
+use anyhow::{anyhow, Error, Result};
+use ds::DataSource;
+use libra::libra_state_view::StateView;
+use libra::libra_types::access_path::AccessPath;
+use libra::libra_types::account_address::AccountAddress;
+use libra::move_core_types::identifier::Identifier;
+use libra::move_core_types::language_storage::{StructTag, TypeTag};
+
+/// Loads the blob of the resource stored under the address.
+/// The resource type is given as a string like `0x1::Account::Balance<0x1::Coins::Coin>`.
+pub fn get_resource<D: DataSource>(
+    ds: &D,
+    address: AccountAddress,
+    type_str: &str,
+) -> Result<Option<Vec<u8>>> {
+    let tag = parse_struct_tag(type_str)?;
+    StateView::get(ds, &AccessPath::new(address, tag.access_vector()))
+}
+
+/// Parses a fully qualified struct type like `0x1::Account::Balance<0x1::Coins::Coin>`.
+/// Type parameters may be primitive types, vectors and structs.
+pub fn parse_struct_tag(type_str: &str) -> Result<StructTag> {
+    let mut parser = TypeParser {
+        source: type_str,
+        rest: type_str,
+    };
+    let tag = parser.struct_tag()?;
+    parser.end()?;
+    Ok(tag)
+}
+
+/// Recursive descent parser of type strings.
+struct TypeParser<'a> {
+    source: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> TypeParser<'a> {
+    fn struct_tag(&mut self) -> Result<StructTag> {
+        let address = self.parse_word("an address", |word| {
+            AccountAddress::from_hex_literal(word).ok()
+        })?;
+        self.expect("::")?;
+        let module = self.identifier()?;
+        self.expect("::")?;
+        let name = self.identifier()?;
+
+        let mut type_params = vec![];
+        if self.eat("<") {
+            loop {
+                type_params.push(self.type_tag()?);
+                if self.eat(">") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        Ok(StructTag {
+            address,
+            module,
+            name,
+            type_params,
+        })
+    }
+
+    fn type_tag(&mut self) -> Result<TypeTag> {
+        let start = self.rest;
+        let word = self.word().ok_or_else(|| self.error("a type"))?;
+        Ok(match word {
+            "u8" => TypeTag::U8,
+            "u64" => TypeTag::U64,
+            "u128" => TypeTag::U128,
+            "bool" => TypeTag::Bool,
+            "address" => TypeTag::Address,
+            "signer" => TypeTag::Signer,
+            "vector" => {
+                self.expect("<")?;
+                let inner = self.type_tag()?;
+                self.expect(">")?;
+                TypeTag::Vector(Box::new(inner))
+            }
+            _ => {
+                self.rest = start;
+                TypeTag::Struct(self.struct_tag()?)
+            }
+        })
+    }
+
+    fn identifier(&mut self) -> Result<Identifier> {
+        self.parse_word("an identifier", |word| Identifier::new(word).ok())
+    }
+
+    /// Parses the next word. The error is reported at the start of the word.
+    fn parse_word<T>(
+        &mut self,
+        expected: &str,
+        parse: impl FnOnce(&'a str) -> Option<T>,
+    ) -> Result<T> {
+        self.rest = self.rest.trim_start();
+        let start = self.rest;
+        match self.word().and_then(parse) {
+            Some(value) => Ok(value),
+            None => {
+                self.rest = start;
+                Err(self.error(expected))
+            }
+        }
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        self.rest = self.rest.trim_start();
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| self.rest.len());
+        if len == 0 {
+            return None;
+        }
+        let (word, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(word)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        if self.rest.starts_with(token) {
+            self.rest = &self.rest[token.len()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", token)))
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        self.rest = self.rest.trim_start();
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("the end of the type"))
+        }
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        anyhow!(
+            "Malformed type '{}': expected {} at position {}.",
+            self.source,
+            expected,
+            self.source.len() - self.rest.len()
+        )
+    }
+}
+
+/// Returns a human-readable form of the `vector<u8>` value.
+/// Valid UTF-8 is returned as is, any other bytes as a `0x`-prefixed hex string.
+pub fn bytes_to_string(bytes: &[u8]) -> String {
+    match str::from_utf8(bytes) {
+        Ok(text) => text.to_owned(),
+        Err(_) => format!("0x{}", hex::encode(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ds::MockDataSource;
+    use libra::libra_types::access_path::AccessPath;
+    use libra::libra_types::account_address::AccountAddress;
+    use libra::move_core_types::identifier::Identifier;
+    use libra::move_core_types::language_storage::{StructTag, TypeTag, CORE_CODE_ADDRESS};
+    use crate::resources::{bytes_to_string, get_resource, parse_struct_tag};
+
+    fn struct_tag(module: &str, name: &str, type_params: Vec<TypeTag>) -> StructTag {
+        StructTag {
+            address: CORE_CODE_ADDRESS,
+            module: Identifier::new(module).unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params,
+        }
+    }
+
+    #[test]
+    fn test_utf8_bytes() {
+        assert_eq!(bytes_to_string(b"Hello, world!"), "Hello, world!");
+        assert_eq!(bytes_to_string("Привет".as_bytes()), "Привет");
+        assert_eq!(bytes_to_string(&[]), "");
+    }
+
+    #[test]
+    fn test_binary_bytes() {
+        assert_eq!(bytes_to_string(&[0xff, 0x00, 0x1a]), "0xff001a");
+        assert_eq!(bytes_to_string(&[0x61, 0x62, 0xc3]), "0x6162c3");
+    }
+
+    #[test]
+    fn test_parse_simple_type() {
+        assert_eq!(
+            parse_struct_tag("0x1::Account::Balance").unwrap(),
+            struct_tag("Account", "Balance", vec![])
+        );
+    }
+
+    #[test]
+    fn test_parse_generic_type() {
+        let coin = struct_tag("Coins", "Coin", vec![TypeTag::U64]);
+        assert_eq!(
+            parse_struct_tag("0x1::Account::Balance<0x1::Coins::Coin<u64>>").unwrap(),
+            struct_tag("Account", "Balance", vec![TypeTag::Struct(coin.clone())])
+        );
+        assert_eq!(
+            parse_struct_tag("0x1::Pool::Pair< vector<u8>, 0x1::Coins::Coin<u64> >").unwrap(),
+            struct_tag(
+                "Pool",
+                "Pair",
+                vec![
+                    TypeTag::Vector(Box::new(TypeTag::U8)),
+                    TypeTag::Struct(coin)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_non_core_type() {
+        let framework = AccountAddress::from_hex_literal("0xdf").unwrap();
+        let tag = parse_struct_tag("0xdf::Block::BlockMetadata<0x1::Coins::Coin>").unwrap();
+        assert_eq!(tag.address, framework);
+        assert_eq!(tag.module.as_str(), "Block");
+        assert_eq!(
+            tag.type_params,
+            vec![TypeTag::Struct(struct_tag("Coins", "Coin", vec![]))]
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_type() {
+        for (type_str, error) in &[
+            ("Account::Balance", "expected an address at position 0"),
+            (" Account::Balance", "expected an address at position 1"),
+            ("0x1::1x::Balance", "expected an identifier at position 5"),
+            ("0x1::Account", "expected '::' at position 12"),
+            ("0x1::Account::Balance<u64", "expected ',' at position 25"),
+            ("0x1::Account::Balance<>", "expected a type at position 22"),
+            (
+                "0x1::Account::Balance u64",
+                "expected the end of the type at position 22",
+            ),
+        ] {
+            let err = parse_struct_tag(type_str).unwrap_err().to_string();
+            assert!(err.contains(error), "{}: {}", type_str, err);
+        }
+    }
+
+    #[test]
+    fn test_get_resource() {
+        let ds = MockDataSource::new();
+        let address = AccountAddress::random();
+        let tag = struct_tag("Account", "Balance", vec![]);
+        ds.insert(AccessPath::new(address, tag.access_vector()), vec![1, 2, 3]);
+
+        assert_eq!(
+            get_resource(&ds, address, "0x1::Account::Balance").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            get_resource(&ds, AccountAddress::random(), "0x1::Account::Balance").unwrap(),
+            None
+        );
+        assert!(get_resource(&ds, address, "0x1::Account").is_err());
+    }
+}